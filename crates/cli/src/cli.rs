@@ -5,17 +5,18 @@ use std::{
   ops::Not,
 };
 
-use anyhow::bail;
 use clap::{command, crate_authors, crate_name, crate_version, CommandFactory, Parser};
 use colored::Colorize;
 use serde::Serialize;
-use snm_config::snm_config::SnmConfig;
+use snm_config::{interaction::Interaction, snm_config::SnmConfig};
 use snm_package_manager::PackageManagerResolver;
 use snm_utils::{consts::SNM_PREFIX, exec::exec_cli};
 use tracing::trace;
 
 use crate::{
-  manage_command::NodeManageCommands, package_manager::Command, snm_command::SnmCommands,
+  manage_command::{NodeManageCommands, PmManageCommands},
+  package_manager::{Command, PackageManagerFactory},
+  snm_command::SnmCommands,
 };
 
 /// SnmCli 是 snm 的命令行工具
@@ -64,6 +65,24 @@ pub struct SnmCli {
         action = clap::ArgAction::Version
     )]
   pub version: Option<bool>,
+
+  #[arg(
+    long,
+    global = true,
+    conflicts_with = "no",
+    help = "Answer yes to every prompt"
+  )]
+  pub yes: bool,
+
+  #[arg(long, global = true, help = "Answer no to every prompt")]
+  pub no: bool,
+
+  #[arg(
+    long,
+    global = true,
+    help = "Print machine-readable JSON instead of text"
+  )]
+  pub json: bool,
 }
 
 impl Display for SnmCli {
@@ -80,6 +99,9 @@ impl From<SnmCommands> for SnmCli {
     Self {
       command,
       version: Some(false),
+      yes: false,
+      no: false,
+      json: false,
     }
   }
 }
@@ -90,14 +112,25 @@ impl SnmCli {
 
     trace!("Get current dir: {:#?}", dir);
 
-    let snm_config = SnmConfig::from(SNM_PREFIX, dir)?;
+    let mut snm_config = SnmConfig::from(SNM_PREFIX, dir)?;
+
+    if self.yes {
+      snm_config.interaction = Interaction::Yes;
+    } else if self.no {
+      snm_config.interaction = Interaction::No;
+    }
+
+    // CI 日志里颜色控制符只会变成乱码
+    if snm_config.ci {
+      colored::control::set_override(false);
+    }
 
     trace!("Get snm config: {:#?}", snm_config);
 
     match self.command {
       SnmCommands::Node { command } => {
         // let nm = snm_node::factory::NodeFactory::new(&snm_config);
-        let nm = crate::node::NodeFactory::new(&snm_config);
+        let nm = crate::node::NodeFactory::new(&snm_config).with_json(self.json);
         match command {
           NodeManageCommands::Default(args) => {
             trace!("Set default node: {:#?}", args);
@@ -115,33 +148,102 @@ impl SnmCli {
             trace!("List node: {:#?}", args);
             nm.list(args).await?;
           }
+          NodeManageCommands::Outdated(args) => {
+            trace!("Outdated node: {:#?}", args);
+            nm.outdated(args).await?;
+          }
+          NodeManageCommands::Upgrade(args) => {
+            trace!("Upgrade node: {:#?}", args);
+            nm.upgrade(args).await?;
+          }
+          NodeManageCommands::Prune(args) => {
+            trace!("Prune node: {:#?}", args);
+            nm.prune(args).await?;
+          }
+          NodeManageCommands::Alias(args) => {
+            trace!("Alias node: {:#?}", args);
+            nm.alias(args).await?;
+          }
+          NodeManageCommands::Unalias(args) => {
+            trace!("Unalias node: {:#?}", args);
+            nm.unalias(args).await?;
+          }
+          NodeManageCommands::Aliases => {
+            trace!("List node aliases");
+            nm.aliases().await?;
+          }
+        }
+      }
+      SnmCommands::Pm { command } => {
+        let pm = PackageManagerFactory::new(&snm_config).with_json(self.json);
+        match command {
+          PmManageCommands::Default(args) => {
+            trace!("Set default package manager: {:#?}", args);
+            pm.set_default(args).await?;
+          }
+          PmManageCommands::Install(args) => {
+            trace!("Install package manager: {:#?}", args);
+            pm.install(args).await?;
+          }
+          PmManageCommands::Uninstall(args) => {
+            trace!("Uninstall package manager: {:#?}", args);
+            pm.uninstall(args).await?;
+          }
+          PmManageCommands::List(args) => {
+            trace!("List package manager: {:#?}", args);
+            pm.list(args).await?;
+          }
         }
       }
       SnmCommands::Install(_) | SnmCommands::Uninstall(_) | SnmCommands::Run(_) => {
+        let ci = snm_config.ci;
+
         let resolver = PackageManagerResolver::from(snm_config);
 
-        let Ok(package_manager) = resolver.find_up_package_manager() else {
-          bail!("You have not correctly configured packageManager in package.json");
-        };
+        let resolved = resolver.resolve_package_manager().await?;
 
-        let handler: Box<dyn Command> = match package_manager.kind() {
-          snm_package_manager::PackageManagerKind::Npm => {
-            Box::new(crate::package_manager::NpmCommandLine::new())
-          }
-          snm_package_manager::PackageManagerKind::Yarn => {
-            if snm_utils::ver::ver_gt_1(&package_manager.version())? {
-              Box::new(crate::package_manager::YarnBerryCommandLine::new())
-            } else {
-              Box::new(crate::package_manager::YarnCommandLine::new())
+        if let Some(fallback) = resolved.source.fallback() {
+          let used = match &resolved.package_manager {
+            Some(package_manager) => format!(
+              "{}@{} ( {} )",
+              package_manager.name(),
+              package_manager.version(),
+              fallback
+            ),
+            None => fallback,
+          };
+          eprintln!(
+            "🔔 packageManager is not declared in package.json, falling back to {}",
+            used.bright_green()
+          );
+        }
+
+        let handler: Box<dyn Command> = match &resolved.package_manager {
+          // Node.js 自带的 npm
+          None => Box::new(crate::package_manager::NpmCommandLine::new()),
+          Some(package_manager) => match package_manager.kind() {
+            snm_package_manager::PackageManagerKind::Npm => {
+              Box::new(crate::package_manager::NpmCommandLine::new())
             }
-          }
-          snm_package_manager::PackageManagerKind::Pnpm => {
-            Box::new(crate::package_manager::PnpmCommandLine::new())
-          }
+            snm_package_manager::PackageManagerKind::Yarn => {
+              if snm_utils::ver::ver_gt_1(&package_manager.version())? {
+                Box::new(crate::package_manager::YarnBerryCommandLine::new())
+              } else {
+                Box::new(crate::package_manager::YarnCommandLine::new())
+              }
+            }
+            snm_package_manager::PackageManagerKind::Pnpm => {
+              Box::new(crate::package_manager::PnpmCommandLine::new())
+            }
+          },
         };
 
         let commands = match self.command {
-          SnmCommands::Install(install_args) => {
+          SnmCommands::Install(mut install_args) => {
+            // CI 中只按 lockfile 安装，lockfile 和 package.json 不一致时直接失败
+            if ci && install_args.package_spec.is_empty() {
+              install_args.frozen = true;
+            }
             trace!("Install command: {:#?}", install_args);
             handler.install(install_args)
           }
@@ -158,6 +260,10 @@ impl SnmCli {
 
         exec_cli(&commands, &vec![], false)?;
       }
+      SnmCommands::Which(args) => {
+        trace!("Which: {:#?}", args);
+        crate::which::which(&snm_config, args, self.json).await?;
+      }
       SnmCommands::SetUp => {
         setup_fig()?;
         setup_symlink()?;
@@ -7,6 +7,7 @@ mod manage_command;
 mod node;
 mod package_manager;
 mod snm_command;
+mod which;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -10,4 +10,28 @@ pub enum NodeManageCommands {
   Uninstall(crate::node::UninstallArgs),
   /// List installed npm versions
   List(crate::node::ListArgs),
+  /// Show installed node versions that have newer patch or minor releases
+  Outdated(crate::node::OutdatedArgs),
+  /// Install the newest release of a major version and make it default
+  Upgrade(crate::node::UpgradeArgs),
+  /// Remove node versions that have not been used for a while
+  Prune(crate::node::PruneArgs),
+  /// Create or update a named alias for a node version
+  Alias(crate::node::AliasArgs),
+  /// Remove a node version alias
+  Unalias(crate::node::UnaliasArgs),
+  /// List node version aliases
+  Aliases,
+}
+
+#[derive(Subcommand, Debug, Serialize)]
+pub enum PmManageCommands {
+  /// Set default package manager version
+  Default(crate::package_manager::PmDefaultArgs),
+  /// Install package manager
+  Install(crate::package_manager::PmInstallArgs),
+  /// Uninstall package manager
+  Uninstall(crate::package_manager::PmUninstallArgs),
+  /// List installed package manager versions
+  List(crate::package_manager::PmListArgs),
 }
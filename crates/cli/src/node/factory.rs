@@ -1,39 +1,115 @@
-use std::{collections::HashMap, fs, ops::Not, path::PathBuf, time::Duration};
-
+use std::{
+  collections::BTreeMap,
+  fs, io,
+  ops::Not,
+  path::{Path, PathBuf},
+  process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context};
+use chrono::Utc;
 use colored::Colorize;
-use dialoguer::Confirm;
 use itertools::Itertools;
 use semver::Version;
 use serde::Serialize;
 use snm_config::snm_config::SnmConfig;
-use snm_downloader::{download_resource, DownloadNodeResource};
-use snm_node::NodeVersion;
+use snm_downloader::{download_archive, download_resource, ArchiveExtension, DownloadNodeResource};
+use snm_node::{
+  NodeAlias, NodeMetadata, NodeResolver, NodeUsage, NodeUsageRecord, NodeVersion, NodeVersionFile,
+  NodeVersionHome, NodeVersionPolicy, NodeVersionReq, ScheduleMetadata, DEFAULT_ALIAS,
+};
+use snm_utils::exec::exec_cli_with_stdout;
 use tracing::trace;
 
-use crate::node::metadata::NodeMetadata;
-
-use super::metadata::ScheduleMetadata;
-
 #[derive(Debug, clap::Args, Serialize)]
 pub struct DefaultArgs {
-  #[arg(help = "Node version")]
+  #[arg(help = "Node version, range or alias such as lts/*, lts/iron, latest")]
   pub version: String,
+
+  #[arg(
+    long,
+    help = "Reinstall global npm packages from this installed node version"
+  )]
+  pub reinstall_packages_from: Option<String>,
 }
 
 #[derive(Debug, clap::Args, Serialize)]
 pub struct ListArgs {
+  #[arg(
+    help = "Only list remote versions matching this major or range, such as 20 or >=18 <22",
+    requires = "remote"
+  )]
+  pub version: Option<String>,
+
   #[arg(long, help = "List remote node", default_value = "false")]
   pub remote: bool,
 
+  #[arg(
+    long,
+    help = "Only list LTS versions",
+    default_value = "false",
+    requires = "remote"
+  )]
+  pub lts: bool,
+
+  #[arg(
+    long,
+    help = "Only list versions of this LTS line, such as iron",
+    requires = "remote"
+  )]
+  pub lts_name: Option<String>,
+
+  #[arg(
+    long,
+    help = "Only list security releases",
+    default_value = "false",
+    requires = "remote"
+  )]
+  pub security_only: bool,
+
+  #[arg(
+    long,
+    help = "Hide versions that reached end of life",
+    default_value = "false",
+    requires = "remote"
+  )]
+  pub hide_eol: bool,
+
+  #[arg(
+    long,
+    help = "Only list versions installed locally",
+    default_value = "false",
+    requires = "remote"
+  )]
+  pub installed: bool,
+
+  #[arg(long, help = "Only list the newest N versions", requires = "remote")]
+  pub limit: Option<usize>,
+
   #[arg(long, help = "Compact mode", default_value = "false")]
   pub compact: bool,
+
+  #[arg(
+    long,
+    help = "Ignore the metadata cache and fetch again",
+    default_value = "false"
+  )]
+  pub refresh: bool,
 }
 
 impl Default for ListArgs {
   fn default() -> Self {
     Self {
+      version: None,
       remote: false,
+      lts: false,
+      lts_name: None,
+      security_only: false,
+      hide_eol: false,
+      installed: false,
+      limit: None,
       compact: true,
+      refresh: false,
     }
   }
 }
@@ -46,13 +122,204 @@ pub struct UninstallArgs {
 
 #[derive(Debug, clap::Args, Serialize)]
 pub struct InstallArgs {
-  #[arg(help = "Node version")]
+  #[arg(
+    help = "Node version, range or alias such as lts/*, lts/iron, latest",
+    required_unless_present_any = ["from_file", "from_url"]
+  )]
+  pub version: Option<String>,
+
+  #[arg(
+    long,
+    help = "Install from a local node archive such as node-v20.11.0-linux-x64.tar.xz",
+    conflicts_with_all = ["version", "from_url", "os", "arch"]
+  )]
+  pub from_file: Option<PathBuf>,
+
+  #[arg(
+    long,
+    help = "Install from a node archive downloaded from this url",
+    conflicts_with_all = ["version", "os", "arch"]
+  )]
+  pub from_url: Option<String>,
+
+  #[arg(
+    long,
+    help = "Expected sha256 of the --from-url archive",
+    requires = "from_url",
+    conflicts_with_all = ["version", "from_file"]
+  )]
+  pub sha256: Option<String>,
+
+  #[arg(long, help = "Target os for cross installs: darwin, linux, win or aix")]
+  pub os: Option<String>,
+
+  #[arg(
+    long,
+    help = "Target arch for cross installs: x64, arm64, armv7l, ppc64le, s390x, riscv64, loong64 ..."
+  )]
+  pub arch: Option<String>,
+
+  #[arg(
+    long,
+    help = "Target libc for linux cross installs: glibc, musl or glibc-217"
+  )]
+  pub libc: Option<String>,
+
+  #[arg(
+    long,
+    help = "Reinstall global npm packages from this installed node version"
+  )]
+  pub reinstall_packages_from: Option<String>,
+}
+
+#[derive(Debug, clap::Args, Serialize)]
+pub struct AliasArgs {
+  #[arg(help = "Alias name, such as work or legacy")]
+  pub name: String,
+
+  #[arg(help = "Node version, range or alias such as lts/*, lts/iron, latest")]
   pub version: String,
 }
 
+#[derive(Debug, clap::Args, Serialize)]
+pub struct UnaliasArgs {
+  #[arg(help = "Alias name")]
+  pub name: String,
+}
+
+/// 解析 `90d`、`12h`、`2w` 这样的时长，返回秒数，不带单位时按天计算
+fn parse_duration_secs(raw: &str) -> Result<u64, String> {
+  let raw = raw.trim();
+
+  let (num, unit) = raw.split_at(raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len()));
+
+  let num = num
+    .parse::<u64>()
+    .map_err(|_| format!("Invalid duration: {}, expected something like 90d", raw))?;
+
+  let unit = match unit {
+    "s" => 1,
+    "m" => 60,
+    "h" => 60 * 60,
+    "d" | "" => 24 * 60 * 60,
+    "w" => 7 * 24 * 60 * 60,
+    _ => {
+      return Err(format!(
+        "Invalid duration unit: {}, expected s, m, h, d or w",
+        raw
+      ))
+    }
+  };
+
+  Ok(num * unit)
+}
+
+#[derive(Debug, clap::Args, Serialize)]
+pub struct PruneArgs {
+  #[arg(
+    long,
+    help = "Remove versions not used for this long, such as 90d, 12h or 2w",
+    default_value = "90d",
+    value_parser = parse_duration_secs
+  )]
+  pub unused_for: u64,
+
+  #[arg(
+    long,
+    help = "Only print what would be removed",
+    default_value = "false"
+  )]
+  pub dry_run: bool,
+}
+
+#[derive(Debug, clap::Args, Serialize)]
+pub struct OutdatedArgs {
+  #[arg(
+    long,
+    help = "Ignore the metadata cache and fetch again",
+    default_value = "false"
+  )]
+  pub refresh: bool,
+}
+
+#[derive(Debug, clap::Args, Serialize)]
+pub struct UpgradeArgs {
+  #[arg(
+    long,
+    help = "Major version to upgrade, defaults to the major of the default node"
+  )]
+  pub major: Option<u64>,
+
+  #[arg(
+    long,
+    help = "Reinstall global npm packages from this installed node version"
+  )]
+  pub reinstall_packages_from: Option<String>,
+
+  #[arg(
+    long,
+    help = "Ignore the metadata cache and fetch again",
+    default_value = "false"
+  )]
+  pub refresh: bool,
+}
+
+/// 命令结果的输出方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+  Text,
+  /// `--json`，每个命令只输出一个 JSON 文档
+  Json,
+  /// 被其他命令调用时不输出，例如 `snm node default` 顺带安装
+  Silent,
+}
+
+/// `snm node install --json` 的输出
+#[derive(Debug, Serialize)]
+struct InstallOutput {
+  version: String,
+  target: String,
+  dir: PathBuf,
+  /// 已安装且没有重装时为 false
+  installed: bool,
+  /// `--from-file` / `--from-url` 使用的归档
+  archive: Option<PathBuf>,
+}
+
+/// `snm node uninstall --json` 的输出
+#[derive(Debug, Serialize)]
+struct UninstallOutput {
+  version: String,
+  dir: PathBuf,
+  /// 版本本来就没有安装时为 false
+  uninstalled: bool,
+  /// 卸载的是 default 版本，default 随之删除
+  was_default: bool,
+}
+
+/// `snm node default --json` 的输出
+#[derive(Debug, Serialize)]
+struct DefaultOutput {
+  version: String,
+  dir: PathBuf,
+  /// 设置 default 前顺带安装了该版本
+  installed: bool,
+}
+
+/// `snm node list --json` 中的本地版本
+#[derive(Debug, Serialize)]
+struct LocalNodeOutput {
+  version: String,
+  dir: PathBuf,
+  default: bool,
+  /// 非 `--compact` 时附带 index.json 和 schedule.json 中的信息
+  metadata: Option<NodeMetadata>,
+}
+
 pub struct NodeFactory<'a> {
   config: &'a SnmConfig,
   default_dir: PathBuf,
+  output: Output,
 }
 
 impl<'a> NodeFactory<'a> {
@@ -60,6 +327,44 @@ impl<'a> NodeFactory<'a> {
     Self {
       config,
       default_dir: config.node_bin_dir.join("default"),
+      output: Output::Text,
+    }
+  }
+
+  /// `--json` 时 list、install、uninstall、default 输出 JSON 文档
+  pub fn with_json(self, json: bool) -> Self {
+    Self {
+      output: if json { Output::Json } else { Output::Text },
+      ..self
+    }
+  }
+
+  /// 其他命令内部调用 install 时使用，JSON 模式下不能多输出一个文档
+  fn nested(&self) -> Self {
+    Self {
+      config: self.config,
+      default_dir: self.default_dir.clone(),
+      output: match self.output {
+        Output::Text => Output::Text,
+        Output::Json | Output::Silent => Output::Silent,
+      },
+    }
+  }
+
+  fn print<T: Serialize>(&self, text: impl FnOnce() -> String, json: &T) -> anyhow::Result<()> {
+    match self.output {
+      Output::Text => println!("{}", text()),
+      Output::Json => println!("{}", serde_json::to_string_pretty(json)?),
+      Output::Silent => {}
+    }
+    Ok(())
+  }
+
+  /// 过程信息，`--json` 时写到 stderr，保证 stdout 只有一个 JSON 文档
+  fn progress(&self, text: impl FnOnce() -> String) {
+    match self.output {
+      Output::Text => println!("{}", text()),
+      Output::Json | Output::Silent => eprintln!("{}", text()),
     }
   }
 
@@ -99,6 +404,21 @@ impl<'a> NodeFactory<'a> {
     Ok(())
   }
 
+  /// default、upgrade 只能作用于本机平台
+  fn ensure_native_platform(&self) -> anyhow::Result<()> {
+    if !self.config.platform.is_native() {
+      bail!(
+        "SNM_PLATFORM_OS / SNM_PLATFORM_ARCH / SNM_PLATFORM_LIBC target {}, which is only supported by `snm node install`",
+        self.config.platform.target()
+      );
+    }
+    Ok(())
+  }
+
+  fn resolver(&self) -> NodeResolver {
+    NodeResolver::from(self.config.clone())
+  }
+
   fn get_node_dir(&self, version: &str) -> PathBuf {
     self.config.node_bin_dir.join(version)
   }
@@ -109,7 +429,168 @@ impl<'a> NodeFactory<'a> {
     Ok(binary_exists)
   }
 
-  async fn get_remote_node(&self) -> anyhow::Result<Vec<NodeMetadata>> {
+  /// 已安装（包括安装不完整）的版本目录，不含 default
+  fn list_installed_dirs(&self) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    let dirs = self
+      .config
+      .node_bin_dir
+      .read_dir()?
+      .filter_map(|entry| entry.ok())
+      .filter(|entry| entry.path().is_dir())
+      .filter_map(|entry| {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        NodeVersion::try_from(name.clone()).ok()?;
+        Some((name, entry.path()))
+      })
+      .sorted_by_cached_key(|(name, _)| Version::parse(name).ok())
+      .collect();
+
+    Ok(dirs)
+  }
+
+  /// 不能被 prune 删除的版本：default、别名指向的版本、已知项目当前声明的版本
+  fn protected_versions(
+    &self,
+    installed: &[Version],
+    usage: &BTreeMap<String, NodeUsageRecord>,
+  ) -> anyhow::Result<BTreeMap<String, String>> {
+    let mut protected = BTreeMap::new();
+
+    let alias = NodeAlias::new(self.config);
+
+    if let Some(nv) = alias.get(DEFAULT_ALIAS)? {
+      protected.insert(nv.val, String::from("default"));
+    }
+
+    for (name, nv) in alias.list()? {
+      protected
+        .entry(nv.val)
+        .or_insert_with(|| format!("alias {}", name));
+    }
+
+    let projects = usage
+      .values()
+      .flat_map(|record| record.projects.keys())
+      .filter(|project| project.is_dir())
+      .unique()
+      .collect::<Vec<_>>();
+
+    for project in projects {
+      let Some(file) = NodeVersionFile::find_in(project, &self.config.node_version_sources)? else {
+        continue;
+      };
+
+      let reason = format!("{:?}", file.path);
+
+      let versions = match NodeVersionReq::try_from(file.raw.clone()) {
+        Ok(NodeVersionReq::Exact(nv)) => vec![nv.val],
+        Ok(NodeVersionReq::Alias { raw }) => {
+          alias.get(&raw)?.map(|nv| nv.val).into_iter().collect()
+        }
+        Ok(req @ NodeVersionReq::Range { .. }) => req
+          .max_satisfying(installed)
+          .map(|v| v.to_string())
+          .into_iter()
+          .collect(),
+        // lts/*、latest 等关键字离线时无法确定，保留该项目用过的所有版本
+        _ => usage
+          .iter()
+          .filter(|(_, record)| record.projects.contains_key(project))
+          .map(|(version, _)| version.to_owned())
+          .collect(),
+      };
+
+      for version in versions {
+        protected.entry(version).or_insert_with(|| reason.clone());
+      }
+    }
+
+    Ok(protected)
+  }
+
+  /// `--reinstall-packages-from` 指定的版本必须已经安装，在下载新版本之前先校验
+  async fn resolve_reinstall_source(
+    &self,
+    from: &Option<String>,
+  ) -> anyhow::Result<Option<NodeVersion>> {
+    let Some(from) = from else {
+      return Ok(None);
+    };
+
+    let req = NodeVersionReq::try_from(from.clone())?;
+
+    let from = self.resolver().resolve_version_req(&req).await?;
+
+    let (_, binary_exists) = self.get_node_binary(&self.get_node_dir(&from.val))?;
+
+    if !binary_exists {
+      bail!(
+        "Node v{} is not installed, can not reinstall packages from it",
+        from.val
+      );
+    }
+
+    Ok(Some(from))
+  }
+
+  /// 用 `to` 版本自带的 npm 重新安装 `from` 版本的全局包
+  fn reinstall_packages(&self, from: &NodeVersion, to: &NodeVersion) -> anyhow::Result<()> {
+    if from.val == to.val {
+      return Ok(());
+    }
+
+    let packages = NodeVersionHome(self.get_node_dir(&from.val)).global_packages()?;
+
+    if packages.is_empty() {
+      self.progress(|| format!("🤔 No global packages found in v{}", &from.val));
+      return Ok(());
+    }
+
+    self.progress(|| {
+      format!(
+        "📦 Reinstalling global packages from v{} into v{}: {}",
+        &from.val,
+        &to.val.bright_green(),
+        packages.join(", ")
+      )
+    });
+
+    let bin_dir = NodeVersionHome(self.get_node_dir(&to.val)).bin_dir();
+
+    let args = ["npm", "install", "--global"]
+      .into_iter()
+      .map(String::from)
+      .chain(packages)
+      .collect::<Vec<_>>();
+
+    let stdout = match self.output {
+      Output::Text => Stdio::inherit(),
+      Output::Json | Output::Silent => Stdio::from(io::stderr()),
+    };
+
+    exec_cli_with_stdout(
+      &args,
+      &[bin_dir.to_string_lossy().into_owned()],
+      false,
+      stdout,
+    )?;
+
+    Ok(())
+  }
+
+  /// index.json 中满足条件的最高正式版本
+  fn newest_stable<F>(remote: &[NodeMetadata], predicate: F) -> Option<(&NodeMetadata, Version)>
+  where
+    F: Fn(&Version) -> bool,
+  {
+    remote
+      .iter()
+      .filter_map(|node| Some((node, node.semver()?)))
+      .filter(|(_, version)| version.pre.is_empty() && predicate(version))
+      .max_by(|a, b| a.1.cmp(&b.1))
+  }
+
+  async fn get_remote_node(&self, refresh: bool) -> anyhow::Result<Vec<NodeMetadata>> {
     let default_version = self
       .config
       .node_bin_dir
@@ -119,18 +600,15 @@ impl<'a> NodeFactory<'a> {
       .and_then(|p| p.file_name().map(|n| n.to_owned()))
       .map(|name| name.to_string_lossy().into_owned());
 
-    let x = ScheduleMetadata::fetch(self.config).await?;
-
-    let node_list_url = format!("{host}/index.json", host = self.config.node_dist_url);
+    let x = ScheduleMetadata::fetch(self.config, refresh).await?;
 
-    let client = reqwest::Client::new();
+    let installed = self
+      .list_installed_dirs()?
+      .into_iter()
+      .map(|(name, _)| name)
+      .collect::<Vec<_>>();
 
-    let node_vec: Vec<NodeMetadata> = client
-      .get(&node_list_url)
-      .timeout(Duration::from_secs(10))
-      .send()
-      .await?
-      .json::<Vec<NodeMetadata>>()
+    let node_vec: Vec<NodeMetadata> = NodeMetadata::fetch(self.config, refresh)
       .await?
       .into_iter()
       .filter_map(|node| {
@@ -141,6 +619,7 @@ impl<'a> NodeFactory<'a> {
           .and_then(|(major, _)| {
             (major != "v0").then(|| NodeMetadata {
               default: default_version.as_ref().map(|v| v.eq(&node.version[1..])),
+              installed: Some(installed.iter().any(|v| v.eq(&node.version[1..]))),
               schedule: x.get(major).map(|s| s.clone()),
               ..node
             })
@@ -152,69 +631,427 @@ impl<'a> NodeFactory<'a> {
     Ok(node_vec)
   }
 
+  /// `snm node list --remote` 的过滤条件，`--limit` 保留最新的 N 个版本
+  fn filter_remote_node(
+    nodes: Vec<NodeMetadata>,
+    args: &ListArgs,
+  ) -> anyhow::Result<Vec<NodeMetadata>> {
+    let req = match &args.version {
+      Some(raw) => match NodeVersionReq::try_from(raw.clone())? {
+        req @ (NodeVersionReq::Exact(_) | NodeVersionReq::Range { .. }) => Some(req),
+        _ => bail!(
+          "Expect a major version or range such as 20 or >=18 <22, got {}",
+          raw
+        ),
+      },
+      None => None,
+    };
+
+    let today = Utc::now().date_naive();
+
+    let nodes = nodes
+      .into_iter()
+      .filter(|node| {
+        req
+          .as_ref()
+          .is_none_or(|req| node.semver().is_some_and(|v| req.matches(&v)))
+      })
+      .filter(|node| !args.lts || node.lts.codename().is_some())
+      .filter(|node| {
+        args.lts_name.as_ref().is_none_or(|name| {
+          node
+            .lts
+            .codename()
+            .is_some_and(|codename| codename.eq_ignore_ascii_case(name.trim()))
+        })
+      })
+      .filter(|node| !args.security_only || node.security)
+      .filter(|node| !args.hide_eol || !node.is_eol(today))
+      .filter(|node| !args.installed || node.installed.unwrap_or(false))
+      .collect::<Vec<_>>();
+
+    let skip = args
+      .limit
+      .map_or(0, |limit| nodes.len().saturating_sub(limit));
+
+    Ok(nodes.into_iter().skip(skip).collect())
+  }
+
   pub async fn set_default(&self, args: DefaultArgs) -> anyhow::Result<()> {
     trace!(r#"Start set default node , args: {:#?}"#, args);
 
-    let node_dir = self.get_node_dir(&args.version);
+    self.ensure_native_platform()?;
+
+    let req = NodeVersionReq::try_from(args.version.clone())?;
+
+    let node_version = self.resolver().resolve_version_req(&req).await?;
+
+    NodeVersionPolicy::from_config(self.config)?.check(&node_version)?;
+
+    let reinstall_from = self
+      .resolve_reinstall_source(&args.reinstall_packages_from)
+      .await?;
 
-    trace!("Directory for Node {}: {:?}", args.version, &node_dir);
+    let node_dir = self.get_node_dir(&node_version.val);
+
+    trace!("Directory for Node {}: {:?}", node_version.val, &node_dir);
 
     let (_, binary_exists) = self.get_node_binary(&node_dir)?;
 
     if !binary_exists {
-      let confirmed = Confirm::new()
-        .with_prompt(format!(
+      // 非交互模式下默认安装，设置 default 本来就需要这个版本
+      let confirmed = self.config.interaction.confirm(
+        format!(
           "🤔 v{} is not installed, do you want to install it ?",
-          &args.version
-        ))
-        .interact()?;
-      if confirmed {
-        self
-          .install(InstallArgs {
-            version: args.version.clone(),
-          })
-          .await?;
+          &node_version.val
+        ),
+        true,
+      )?;
+      // 拒绝安装时不能让 default 指向一个不存在的版本
+      if !confirmed {
+        bail!(
+          "v{} is not installed, the default Node.js is unchanged",
+          &node_version.val
+        );
       }
+
+      self
+        .nested()
+        .install(InstallArgs {
+          version: Some(node_version.val.clone()),
+          from_file: None,
+          from_url: None,
+          sha256: None,
+          reinstall_packages_from: None,
+          os: None,
+          arch: None,
+          libc: None,
+        })
+        .await?;
     }
 
     self.symlink_default(&node_dir, &self.default_dir)?;
 
-    println!("🎉 Node v{} is now default", &args.version.bright_green());
+    self.print(
+      || {
+        format!(
+          "🎉 Node v{} is now default",
+          &node_version.val.bright_green()
+        )
+      },
+      &DefaultOutput {
+        version: node_version.val.clone(),
+        dir: node_dir.clone(),
+        installed: !binary_exists,
+      },
+    )?;
+
+    if let Some(from) = reinstall_from {
+      self.reinstall_packages(&from, &node_version)?;
+    }
 
     Ok(())
   }
 
   pub async fn install(&self, args: InstallArgs) -> anyhow::Result<()> {
-    let node_dir = self.get_node_dir(&args.version);
+    let archive = match (&args.from_file, &args.from_url) {
+      (Some(file), _) => Some(file.clone()),
+      (None, Some(url)) => Some(self.download_archive(url, args.sha256.clone()).await?),
+      (None, None) => None,
+    };
+
+    if let Some(archive) = archive {
+      return self
+        .install_archive(&archive, &args.reinstall_packages_from)
+        .await;
+    }
 
-    let (_, binary_exists) = self.get_node_binary(&node_dir)?;
+    let Some(version) = args.version.clone() else {
+      bail!("Node version is required, or use --from-file / --from-url");
+    };
 
-    if binary_exists {
-      let confirm = Confirm::new()
-        .with_prompt(format!(
-          "🤔 v{} is already installed, do you want to reinstall it ?",
-          &args.version
-        ))
-        .interact()?;
+    let req = NodeVersionReq::try_from(version)?;
 
-      if confirm {
-        fs::remove_dir_all(&node_dir)?;
-      } else {
-        return Ok(());
+    // lts/*、latest、范围等都要先落到具体版本，node_bin_dir 下的目录始终以精确版本命名
+    let node_version = self.resolver().resolve_remote_version_req(&req).await?;
+
+    NodeVersionPolicy::from_config(self.config)?.check(&node_version)?;
+
+    let platform = self.config.platform.with_override(
+      args.os.as_deref(),
+      args.arch.as_deref(),
+      args.libc.as_deref(),
+    )?;
+
+    if !platform.is_native() {
+      if args.reinstall_packages_from.is_some() {
+        bail!("--reinstall-packages-from can not be used for cross installs");
       }
+
+      let config = SnmConfig {
+        platform,
+        ..self.config.clone()
+      };
+
+      return self.install_cross_target(&config, &node_version.val).await;
+    }
+
+    let reinstall_from = self
+      .resolve_reinstall_source(&args.reinstall_packages_from)
+      .await?;
+
+    let node_dir = self.get_node_dir(&node_version.val);
+
+    let installed = self.confirm_reinstall(&node_version, &node_dir)?;
+
+    if installed {
+      let resource = DownloadNodeResource::builder()
+        .config(self.config)
+        .bin_name(String::from("node"))
+        .version(node_version.val.clone())
+        .build();
+
+      download_resource(resource).await?;
+    }
+
+    self.print(
+      || {
+        if installed {
+          format!("🎉 Node v{} is installed", &node_version.val.bright_green())
+        } else {
+          format!(
+            "🤔 v{} is already installed, skipped",
+            &node_version.val.bright_green()
+          )
+        }
+      },
+      &InstallOutput {
+        version: node_version.val.clone(),
+        target: self.config.platform.for_version(&node_version.val).target(),
+        dir: node_dir.clone(),
+        installed,
+        archive: None,
+      },
+    )?;
+
+    if let Some(from) = reinstall_from {
+      self.reinstall_packages(&from, &node_version)?;
+    }
+
+    Ok(())
+  }
+
+  /// 版本已安装时询问是否重装，确认后删除旧目录，非交互模式下保留已安装的版本
+  fn confirm_reinstall(
+    &self,
+    node_version: &NodeVersion,
+    node_dir: &PathBuf,
+  ) -> anyhow::Result<bool> {
+    let (_, binary_exists) = self.get_node_binary(node_dir)?;
+
+    if !binary_exists {
+      return Ok(true);
+    }
+
+    let confirm = self.config.interaction.confirm(
+      format!(
+        "🤔 v{} is already installed, do you want to reinstall it ?",
+        &node_version.val
+      ),
+      false,
+    )?;
+
+    if confirm {
+      fs::remove_dir_all(node_dir)?;
+    }
+
+    Ok(confirm)
+  }
+
+  /// `--from-url` 的归档下载到 download_dir/node/archives 下，文件名取 url 的最后一段
+  async fn download_archive(&self, url: &str, sha256: Option<String>) -> anyhow::Result<PathBuf> {
+    let file_name = url
+      .split(['?', '#'])
+      .next()
+      .and_then(|url| url.trim_end_matches('/').rsplit('/').next())
+      .filter(|name| !name.is_empty())
+      .with_context(|| format!("Can not get the archive name from {}", url))?;
+
+    if self.config.offline {
+      bail!(
+        "Offline mode is enabled ( SNM_OFFLINE ), {} would be downloaded from {}, use --from-file instead",
+        file_name,
+        url
+      );
+    }
+
+    let target = self
+      .config
+      .download_dir
+      .join("node")
+      .join("archives")
+      .join(file_name);
+
+    if target.try_exists()? {
+      fs::remove_file(&target)?;
+    }
+
+    download_archive(url, target, sha256).await
+  }
+
+  /// 安装不在 index.json 中的 Node.js 归档，例如内部打过补丁的构建
+  ///
+  /// 先解压到 node_bin_dir 下的临时目录，版本号取自归档名，取不到时执行 `node --version`，
+  /// 最后把临时目录移动到 node_bin_dir/<version>
+  async fn install_archive(
+    &self,
+    archive: &Path,
+    reinstall_packages_from: &Option<String>,
+  ) -> anyhow::Result<()> {
+    if !archive.try_exists()? {
+      bail!("Node archive not found: {:?}", archive);
     }
 
-    let node_version = NodeVersion::try_from(args.version.clone())?;
+    let reinstall_from = self
+      .resolve_reinstall_source(reinstall_packages_from)
+      .await?;
+
+    let staging_dir = self
+      .config
+      .node_bin_dir
+      .join(format!(".archive-{}", std::process::id()));
+
+    let installed = self.install_staged_archive(archive, &staging_dir);
+
+    if staging_dir.try_exists()? {
+      fs::remove_dir_all(&staging_dir)?;
+    }
+
+    let (node_version, installed) = installed?;
+
+    self.print(
+      || {
+        if installed {
+          format!(
+            "🎉 Node v{} is installed from {:?}",
+            &node_version.val.bright_green(),
+            archive
+          )
+        } else {
+          format!(
+            "🤔 v{} is already installed, skipped",
+            &node_version.val.bright_green()
+          )
+        }
+      },
+      &InstallOutput {
+        version: node_version.val.clone(),
+        target: self.config.platform.for_version(&node_version.val).target(),
+        dir: self.get_node_dir(&node_version.val),
+        installed,
+        archive: Some(archive.to_path_buf()),
+      },
+    )?;
+
+    if !installed {
+      return Ok(());
+    }
+
+    if let Some(from) = reinstall_from {
+      self.reinstall_packages(&from, &node_version)?;
+    }
+
+    Ok(())
+  }
+
+  /// 返回归档中的版本以及是否安装，用户取消重装时不安装
+  fn install_staged_archive(
+    &self,
+    archive: &Path,
+    staging_dir: &PathBuf,
+  ) -> anyhow::Result<(NodeVersion, bool)> {
+    ArchiveExtension::from_path(archive.to_path_buf())?.decompress(staging_dir)?;
+
+    let node_version = match NodeVersion::from_archive_name(archive) {
+      Some(node_version) => node_version,
+      None => self.probe_node_version(staging_dir)?,
+    };
+
+    NodeVersionPolicy::from_config(self.config)?.check(&node_version)?;
+
+    let node_dir = self.get_node_dir(&node_version.val);
+
+    if !self.confirm_reinstall(&node_version, &node_dir)? {
+      return Ok((node_version, false));
+    }
+
+    fs::rename(staging_dir, &node_dir)?;
+
+    Ok((node_version, true))
+  }
+
+  /// 归档名里没有版本号时，执行解压出来的 `node --version`
+  fn probe_node_version(&self, node_dir: &PathBuf) -> anyhow::Result<NodeVersion> {
+    let (binary, binary_exists) = self.get_node_binary(node_dir)?;
+
+    if !binary_exists {
+      bail!(
+        "The archive does not look like a Node.js build, {:?} not found",
+        binary
+      );
+    }
+
+    let output = Command::new(&binary)
+      .arg("--version")
+      .output()
+      .with_context(|| format!("Failed to run {:?} --version", binary))?;
+
+    if !output.status.success() {
+      bail!(
+        "Failed to run {:?} --version: {}",
+        binary,
+        String::from_utf8_lossy(&output.stderr).trim()
+      );
+    }
+
+    NodeVersion::try_from(String::from_utf8_lossy(&output.stdout).into_owned())
+  }
+
+  /// 为其他平台准备 Node.js，装到 node_target_dir 下按 target 区分的目录，不影响本机
+  async fn install_cross_target(&self, config: &SnmConfig, version: &str) -> anyhow::Result<()> {
+    let target = config.platform.target();
+
+    let node_dir = config.node_home_dir(version);
+
+    if node_dir.try_exists()? {
+      fs::remove_dir_all(&node_dir)?;
+    }
 
     let resource = DownloadNodeResource::builder()
-      .config(self.config)
+      .config(config)
       .bin_name(String::from("node"))
-      .version(node_version.val.clone())
+      .version(version.to_string())
       .build();
 
     download_resource(resource).await?;
 
-    println!("🎉 Node v{} is installed", &node_version.val.bright_green());
+    self.print(
+      || {
+        format!(
+          "🎉 Node v{} ( {} ) is installed into {:?}",
+          version.bright_green(),
+          target,
+          node_dir
+        )
+      },
+      &InstallOutput {
+        version: version.to_string(),
+        target: target.clone(),
+        dir: node_dir.clone(),
+        installed: true,
+        archive: None,
+      },
+    )?;
 
     Ok(())
   }
@@ -225,17 +1062,20 @@ impl<'a> NodeFactory<'a> {
     let (_, binary_exists) = self.get_node_binary(&node_dir)?;
 
     if !binary_exists {
-      println!("🤔 v{} is not installed", &args.version.bright_green());
-      return Ok(());
+      return self.print(
+        || format!("🤔 v{} is not installed", &args.version.bright_green()),
+        &UninstallOutput {
+          version: args.version.clone(),
+          dir: node_dir.clone(),
+          uninstalled: false,
+          was_default: false,
+        },
+      );
     }
 
-    let default_dir_exists = self.has_default()?;
-
-    if default_dir_exists {
+    let was_default = self.has_default()? && {
       let link = self.default_dir.read_link()?;
 
-      let eq = link.eq(&node_dir);
-
       trace!(
         r#"Symlink Relation: 
 {:?} -> {:?}"#,
@@ -243,27 +1083,52 @@ impl<'a> NodeFactory<'a> {
         &link
       );
 
-      if eq {
-        fs::remove_dir_all(&node_dir)?;
-        fs::remove_dir_all(&self.default_dir)?;
-        println!(
-          "🎉 Node v{} is uninstalled , Now there is no default node .",
-          &args.version.bright_green()
-        );
-      }
-    } else {
-      fs::remove_dir_all(&node_dir)?;
-      println!("🎉 Node v{} is uninstalled", &args.version.bright_green());
+      link.eq(&node_dir)
+    };
+
+    fs::remove_dir_all(&node_dir)?;
+
+    if was_default {
+      fs::remove_dir_all(&self.default_dir)?;
     }
 
-    Ok(())
+    self.print(
+      || {
+        if was_default {
+          format!(
+            "🎉 Node v{} is uninstalled , Now there is no default node .",
+            &args.version.bright_green()
+          )
+        } else {
+          format!("🎉 Node v{} is uninstalled", &args.version.bright_green())
+        }
+      },
+      &UninstallOutput {
+        version: args.version.clone(),
+        dir: node_dir.clone(),
+        uninstalled: true,
+        was_default,
+      },
+    )
   }
 
   pub async fn list(&self, args: ListArgs) -> anyhow::Result<()> {
     trace!(r#"Start show node list , args: {:#?}"#, args);
 
     if args.remote {
-      let remote_node_list = self.get_remote_node().await?;
+      let remote_node_list =
+        Self::filter_remote_node(self.get_remote_node(args.refresh).await?, &args)?;
+
+      if self.output == Output::Json {
+        println!("{}", serde_json::to_string_pretty(&remote_node_list)?);
+        return Ok(());
+      }
+
+      if remote_node_list.is_empty() {
+        println!("😿 No remote node matches");
+        return Ok(());
+      }
+
       remote_node_list.into_iter().for_each(|node| {
         println!("{}", node);
       });
@@ -285,9 +1150,8 @@ impl<'a> NodeFactory<'a> {
       .config
       .node_bin_dir
       .read_dir()
-      .ok()
-      .and_then(|dir| {
-        let responses = dir
+      .map(|dir| {
+        dir
           .filter_map(|dir| dir.ok())
           .map(|dir| dir.path())
           .filter(|path| path.is_dir())
@@ -298,37 +1162,57 @@ impl<'a> NodeFactory<'a> {
           })
           .filter(|v| v.eq("default").not())
           .sorted_by_cached_key(|v| Version::parse(v).ok())
-          .collect::<Vec<String>>();
-
-        if responses.is_empty() {
-          println!("😿 Local node list is empty");
-          return None;
-        }
-
-        Some(responses)
+          .collect::<Vec<String>>()
       })
       .unwrap_or_default();
 
     trace!("local_node_list---->: {:?}", &local_node_list);
 
-    if args.compact {
+    let is_default = |v: &String| default_version.as_ref().is_some_and(|d_v| v.eq(d_v));
+
+    let json = self.output == Output::Json;
+
+    if !json && local_node_list.is_empty() {
+      println!("😿 Local node list is empty");
+      return Ok(());
+    }
+
+    if !json && args.compact {
       local_node_list.into_iter().for_each(|v| {
-        let is_default = default_version.as_ref().map_or(false, |d_v| v.eq(d_v));
-        let prefix = if is_default { "->" } else { "" }.bright_green();
+        let prefix = if is_default(&v) { "->" } else { "" }.bright_green();
         println!("{:<2} {}", prefix, v);
       });
       return Ok(());
     }
 
-    let mut remote_node_map = self
-      .get_remote_node()
-      .await?
-      .into_iter()
-      .map(|node| (node.version[1..].to_string(), node))
-      .collect::<HashMap<String, NodeMetadata>>();
+    let mut remote_node_map = if args.compact || local_node_list.is_empty() {
+      BTreeMap::new()
+    } else {
+      self
+        .get_remote_node(args.refresh)
+        .await?
+        .into_iter()
+        .map(|node| (node.version[1..].to_string(), node))
+        .collect::<BTreeMap<String, NodeMetadata>>()
+    };
 
     trace!(r#"Node remote info{:#?}"#, remote_node_map);
 
+    if json {
+      let nodes = local_node_list
+        .into_iter()
+        .map(|v| LocalNodeOutput {
+          dir: self.get_node_dir(&v),
+          default: is_default(&v),
+          metadata: remote_node_map.remove(&v),
+          version: v,
+        })
+        .collect::<Vec<_>>();
+
+      println!("{}", serde_json::to_string_pretty(&nodes)?);
+      return Ok(());
+    }
+
     local_node_list
       .into_iter()
       .filter_map(|v| remote_node_map.remove(&v))
@@ -338,4 +1222,284 @@ impl<'a> NodeFactory<'a> {
 
     Ok(())
   }
+
+  pub async fn prune(&self, args: PruneArgs) -> anyhow::Result<()> {
+    trace!(r#"Start prune node , args: {:#?}"#, args);
+
+    let usage = NodeUsage::new(self.config);
+
+    let records = usage.load();
+
+    let installed = self.list_installed_dirs()?;
+
+    let installed_versions = installed
+      .iter()
+      .filter_map(|(name, _)| Version::parse(name).ok())
+      .collect::<Vec<_>>();
+
+    let protected = self.protected_versions(&installed_versions, &records)?;
+
+    let now = NodeUsage::now();
+
+    let mut removed = 0;
+
+    for (version, dir) in installed {
+      // 没有使用记录的版本按安装时间计算
+      let last_used = match records.get(&version) {
+        Some(record) => record.last_used,
+        None => dir
+          .metadata()
+          .and_then(|meta| meta.modified())
+          .ok()
+          .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+          .map(|d| d.as_secs())
+          .unwrap_or(now),
+      };
+
+      let idle_days = now.saturating_sub(last_used) / (24 * 60 * 60);
+
+      if let Some(reason) = protected.get(&version) {
+        trace!("Keep v{}, referenced by {}", version, reason);
+        continue;
+      }
+
+      if now.saturating_sub(last_used) < args.unused_for {
+        continue;
+      }
+
+      removed += 1;
+
+      if args.dry_run {
+        println!(
+          "🔍 v{} would be removed ( unused for {} days )",
+          version.bright_green(),
+          idle_days
+        );
+        continue;
+      }
+
+      fs::remove_dir_all(&dir)?;
+
+      let archive_dir = self.config.download_dir.join("node").join(&version);
+      if archive_dir.try_exists()? {
+        fs::remove_dir_all(&archive_dir)?;
+      }
+
+      usage.remove(&version)?;
+
+      println!(
+        "🗑️  v{} is removed ( unused for {} days )",
+        version.bright_green(),
+        idle_days
+      );
+    }
+
+    if removed == 0 {
+      println!("🎉 Nothing to prune");
+    }
+
+    Ok(())
+  }
+
+  pub async fn outdated(&self, args: OutdatedArgs) -> anyhow::Result<()> {
+    trace!(r#"Start check outdated node , args: {:#?}"#, args);
+
+    let installed = self
+      .list_installed_dirs()?
+      .into_iter()
+      .filter_map(|(name, _)| Version::parse(&name).ok())
+      .filter(|version| version.pre.is_empty())
+      .collect::<Vec<_>>();
+
+    if installed.is_empty() {
+      println!("😿 Local node list is empty");
+      return Ok(());
+    }
+
+    let default_version = NodeAlias::new(self.config)
+      .get(DEFAULT_ALIAS)?
+      .map(|nv| nv.val);
+
+    let remote = NodeMetadata::fetch(self.config, args.refresh).await?;
+
+    for version in installed {
+      let newest_patch = Self::newest_stable(&remote, |v| {
+        v.major == version.major && v.minor == version.minor
+      })
+      .map(|(_, v)| v);
+
+      let newest_minor = Self::newest_stable(&remote, |v| v.major == version.major).map(|(_, v)| v);
+
+      // 当前版本之后、同一大版本内是否有安全更新
+      let security = remote
+        .iter()
+        .filter(|node| node.security)
+        .filter_map(|node| node.semver())
+        .any(|v| v.major == version.major && v.pre.is_empty() && v > version);
+
+      let is_default = default_version
+        .as_ref()
+        .is_some_and(|d| d.eq(&version.to_string()));
+
+      let prefix = if is_default { "->" } else { "" }.bright_green();
+
+      let outdated = newest_minor.as_ref().is_some_and(|v| v > &version);
+
+      if !outdated {
+        println!(
+          "{:<2} {:<12} {}",
+          prefix,
+          version.to_string(),
+          "up to date".bright_black()
+        );
+        continue;
+      }
+
+      let show = |v: Option<Version>| {
+        v.filter(|v| v > &version)
+          .map(|v| v.to_string())
+          .unwrap_or(String::from("-"))
+      };
+
+      println!(
+        "{:<2} {:<12} patch {:<12} minor {:<12} {}",
+        prefix,
+        version.to_string(),
+        show(newest_patch).bright_green(),
+        show(newest_minor).bright_green(),
+        if security {
+          "security".bright_red()
+        } else {
+          "".normal()
+        }
+      );
+    }
+
+    Ok(())
+  }
+
+  pub async fn upgrade(&self, args: UpgradeArgs) -> anyhow::Result<()> {
+    trace!(r#"Start upgrade node , args: {:#?}"#, args);
+
+    self.ensure_native_platform()?;
+
+    let major = match args.major {
+      Some(major) => major,
+      None => {
+        let Some(nv) = NodeAlias::new(self.config).get(DEFAULT_ALIAS)? else {
+          bail!("No default Node.js version found, use `snm node upgrade --major <N>`");
+        };
+        Version::parse(&nv.val)?.major
+      }
+    };
+
+    let remote = NodeMetadata::fetch(self.config, args.refresh).await?;
+
+    let Some((node, newest)) = Self::newest_stable(&remote, |v| v.major == major) else {
+      bail!("No Node.js release found for major version {}", major);
+    };
+
+    let node_version = NodeVersion::try_from(newest.to_string())?;
+
+    NodeVersionPolicy::from_config(self.config)?.check(&node_version)?;
+
+    let reinstall_from = self
+      .resolve_reinstall_source(&args.reinstall_packages_from)
+      .await?;
+
+    let node_dir = self.get_node_dir(&node_version.val);
+
+    let (_, binary_exists) = self.get_node_binary(&node_dir)?;
+
+    if !binary_exists {
+      self
+        .nested()
+        .install(InstallArgs {
+          version: Some(node_version.val.clone()),
+          from_file: None,
+          from_url: None,
+          sha256: None,
+          reinstall_packages_from: None,
+          os: None,
+          arch: None,
+          libc: None,
+        })
+        .await?;
+    }
+
+    self.symlink_default(&node_dir, &self.default_dir)?;
+
+    println!(
+      "🎉 Node v{} is now default{}",
+      &node_version.val.bright_green(),
+      if node.security {
+        " ( security release )".bright_red()
+      } else {
+        "".normal()
+      }
+    );
+
+    if let Some(from) = reinstall_from {
+      self.reinstall_packages(&from, &node_version)?;
+    }
+
+    Ok(())
+  }
+
+  pub async fn alias(&self, args: AliasArgs) -> anyhow::Result<()> {
+    let req = NodeVersionReq::try_from(args.version.clone())?;
+
+    let node_version = self.resolver().resolve_version_req(&req).await?;
+
+    NodeAlias::new(self.config).set(&args.name, &node_version)?;
+
+    println!(
+      "🎉 {} -> v{}",
+      &args.name.bright_green(),
+      &node_version.val.bright_green()
+    );
+
+    Ok(())
+  }
+
+  pub async fn unalias(&self, args: UnaliasArgs) -> anyhow::Result<()> {
+    if NodeAlias::new(self.config).remove(&args.name)? {
+      println!("🎉 Alias {} is removed", &args.name.bright_green());
+    } else {
+      println!("🤔 Alias {} does not exist", &args.name.bright_green());
+    }
+
+    Ok(())
+  }
+
+  pub async fn aliases(&self) -> anyhow::Result<()> {
+    let alias = NodeAlias::new(self.config);
+
+    let aliases = alias
+      .get(DEFAULT_ALIAS)?
+      .map(|nv| (DEFAULT_ALIAS.to_string(), nv))
+      .into_iter()
+      .chain(alias.list()?)
+      .collect::<Vec<_>>();
+
+    if aliases.is_empty() {
+      println!("😿 Node alias list is empty");
+      return Ok(());
+    }
+
+    aliases.into_iter().for_each(|(name, nv)| {
+      let (_, installed) = self
+        .get_node_binary(&self.get_node_dir(&nv.val))
+        .unwrap_or_default();
+      let suffix = if installed { "" } else { "( not installed )" };
+      println!(
+        "{:<12} -> {:<12} {}",
+        name.bright_green(),
+        nv.val,
+        suffix.bright_black()
+      );
+    });
+
+    Ok(())
+  }
 }
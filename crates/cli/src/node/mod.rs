@@ -1,5 +1,3 @@
 mod factory;
-mod lts;
-mod metadata;
 
 pub use factory::*;
@@ -1,19 +1,439 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  str::FromStr,
+};
+
+use anyhow::{bail, Context};
+use colored::Colorize;
+use semver::Version;
+use serde::Serialize;
 use snm_config::snm_config::SnmConfig;
+use snm_downloader::{download_resource, DownloadPackageManagerResource};
+use snm_package_manager::{
+  PackageManager, PackageManagerKind, PackageManagerResolver, RemoteVersions,
+};
+use tracing::trace;
+
+const KINDS: [PackageManagerKind; 3] = [
+  PackageManagerKind::Npm,
+  PackageManagerKind::Pnpm,
+  PackageManagerKind::Yarn,
+];
+
+fn parse_kind(raw: &str) -> Result<PackageManagerKind, String> {
+  PackageManagerKind::from_str(raw.trim()).map_err(|_| {
+    format!(
+      "Unsupported package manager: {}, expected npm, yarn or pnpm",
+      raw
+    )
+  })
+}
+
+/// 删除 `snm pm default` 的链接，windows 下是 `symlink_dir` 创建的，需要 `remove_dir`
+fn remove_default_link(path: &Path) -> anyhow::Result<()> {
+  #[cfg(unix)]
+  fs::remove_file(path)?;
+
+  #[cfg(windows)]
+  fs::remove_dir(path)?;
+
+  Ok(())
+}
+
+#[derive(Debug, clap::Args, Serialize)]
+pub struct PmInstallArgs {
+  #[arg(
+    help = "Package manager with a version, range or dist-tag, such as pnpm@9.1.0, pnpm@9 or yarn@latest"
+  )]
+  pub package_manager: String,
+
+  #[arg(
+    long,
+    help = "Ignore the metadata cache and fetch again",
+    default_value = "false"
+  )]
+  pub refresh: bool,
+}
+
+#[derive(Debug, clap::Args, Serialize)]
+pub struct PmUninstallArgs {
+  #[arg(help = "Installed package manager, such as pnpm@9.1.0")]
+  pub package_manager: String,
+}
+
+#[derive(Debug, clap::Args, Serialize)]
+pub struct PmDefaultArgs {
+  #[arg(help = "Package manager with a version, range or dist-tag, such as pnpm@9.1.0 or pnpm@9")]
+  pub package_manager: String,
+}
+
+#[derive(Debug, clap::Args, Serialize)]
+pub struct PmListArgs {
+  #[arg(
+    help = "Only list this package manager: npm, yarn or pnpm",
+    value_parser = parse_kind,
+    required_if_eq("remote", "true")
+  )]
+  #[serde(skip)]
+  pub kind: Option<PackageManagerKind>,
+
+  #[arg(
+    long,
+    help = "List versions published to the registry",
+    default_value = "false"
+  )]
+  pub remote: bool,
+
+  #[arg(
+    long,
+    help = "Include prerelease versions",
+    default_value = "false",
+    requires = "remote"
+  )]
+  pub prerelease: bool,
+
+  #[arg(long, help = "Only list the newest N versions", requires = "remote")]
+  pub limit: Option<usize>,
+
+  #[arg(
+    long,
+    help = "Ignore the metadata cache and fetch again",
+    default_value = "false"
+  )]
+  pub refresh: bool,
+}
+
+/// `snm pm list --json` 中的一项
+#[derive(Debug, Serialize)]
+struct PmVersionOutput {
+  name: String,
+  version: String,
+  dir: PathBuf,
+  default: bool,
+  installed: bool,
+}
+
+/// `snm pm install/uninstall/default --json` 的输出
+#[derive(Debug, Serialize)]
+struct PmActionOutput {
+  name: String,
+  version: String,
+  dir: PathBuf,
+  /// install：本次是否下载安装；uninstall：是否删除；default：是否顺带安装
+  changed: bool,
+  default: bool,
+}
 
+/// 管理 node_modules_dir/<name>/<version> 下的包管理器，和 `NodeFactory` 管理 Node.js 的方式一致
 pub struct PackageManagerFactory<'a> {
   config: &'a SnmConfig,
+  json: bool,
 }
 
 impl<'a> PackageManagerFactory<'a> {
   pub fn new(config: &'a SnmConfig) -> Self {
-    Self { config }
+    Self {
+      config,
+      json: false,
+    }
+  }
+
+  /// `--json` 时输出 JSON 文档
+  pub fn with_json(self, json: bool) -> Self {
+    Self { json, ..self }
+  }
+
+  fn resolver(&self) -> PackageManagerResolver {
+    PackageManagerResolver::from(self.config.clone())
+  }
+
+  fn is_installed(&self, package_manager: &PackageManager) -> anyhow::Result<bool> {
+    Ok(
+      self
+        .resolver()
+        .package_manager_dir(package_manager)
+        .join("package.json")
+        .try_exists()?,
+    )
+  }
+
+  fn is_default(&self, package_manager: &PackageManager) -> bool {
+    self
+      .resolver()
+      .default_package_manager(&package_manager.kind())
+      .is_some_and(|default| default.version() == package_manager.version())
+  }
+
+  fn print_action(
+    &self,
+    text: String,
+    package_manager: &PackageManager,
+    changed: bool,
+  ) -> anyhow::Result<()> {
+    if !self.json {
+      println!("{}", text);
+      return Ok(());
+    }
+
+    let output = PmActionOutput {
+      name: package_manager.name().to_string(),
+      version: package_manager.version().to_string(),
+      dir: self.resolver().package_manager_dir(package_manager),
+      changed,
+      default: self.is_default(package_manager),
+    };
+
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+  }
+
+  /// `pnpm@9.1.0` 原样返回，`pnpm@9`、`pnpm@^8.6`、`yarn@latest` 对照 registry 解析为具体版本
+  async fn resolve(&self, raw: &str, refresh: bool) -> anyhow::Result<PackageManager> {
+    let package_manager = PackageManager::from_str(raw.trim())?;
+
+    if Version::parse(package_manager.version()).is_ok() {
+      return Ok(package_manager);
+    }
+
+    let kind = package_manager.kind();
+    let spec = package_manager.version();
+
+    let remote = RemoteVersions::fetch(self.config, &kind, refresh).await?;
+
+    let version = remote
+      .resolve(spec)?
+      .with_context(|| format!("No published version matches {}", raw))?;
+
+    trace!("Resolved {} to {}@{}", raw, kind, version);
+
+    Ok(PackageManager::new(kind, version))
+  }
+
+  async fn download(&self, package_manager: &PackageManager) -> anyhow::Result<PathBuf> {
+    let resource = DownloadPackageManagerResource::builder()
+      .config(self.config)
+      .bin_name(package_manager.name().to_string())
+      .version(package_manager.version().to_string())
+      .pinned_digest(package_manager.hash().map(|hash| hash.pinned_digest()))
+      .build();
+
+    download_resource(resource).await
+  }
+
+  pub async fn install(&self, args: PmInstallArgs) -> anyhow::Result<()> {
+    let package_manager = self.resolve(&args.package_manager, args.refresh).await?;
+
+    let dir = self.resolver().package_manager_dir(&package_manager);
+
+    let installed = if self.is_installed(&package_manager)? {
+      // 非交互模式下默认不重装
+      let confirm = self.config.interaction.confirm(
+        format!(
+          "🤔 {}@{} is already installed, do you want to reinstall it ?",
+          package_manager.name(),
+          package_manager.version()
+        ),
+        false,
+      )?;
+
+      if confirm {
+        fs::remove_dir_all(&dir)?;
+      }
+
+      confirm
+    } else {
+      true
+    };
+
+    if installed {
+      self.download(&package_manager).await?;
+    }
+
+    let label = format!("{}@{}", package_manager.name(), package_manager.version());
+
+    self.print_action(
+      if installed {
+        format!("🎉 {} is installed", label.bright_green())
+      } else {
+        format!("🤔 {} is already installed, skipped", label.bright_green())
+      },
+      &package_manager,
+      installed,
+    )
+  }
+
+  pub async fn uninstall(&self, args: PmUninstallArgs) -> anyhow::Result<()> {
+    let package_manager = PackageManager::from_str(args.package_manager.trim())?;
+
+    let label = format!("{}@{}", package_manager.name(), package_manager.version());
+
+    if !self.is_installed(&package_manager)? {
+      return self.print_action(
+        format!("🤔 {} is not installed", label.bright_green()),
+        &package_manager,
+        false,
+      );
+    }
+
+    let was_default = self.is_default(&package_manager);
+
+    fs::remove_dir_all(self.resolver().package_manager_dir(&package_manager))?;
+
+    if was_default {
+      remove_default_link(&self.resolver().default_dir(&package_manager.kind()))?;
+    }
+
+    self.print_action(
+      if was_default {
+        format!(
+          "🎉 {} is uninstalled , Now there is no default {} .",
+          label.bright_green(),
+          package_manager.name()
+        )
+      } else {
+        format!("🎉 {} is uninstalled", label.bright_green())
+      },
+      &package_manager,
+      true,
+    )
   }
 
-  pub async fn install() {}
+  pub async fn set_default(&self, args: PmDefaultArgs) -> anyhow::Result<()> {
+    let package_manager = self.resolve(&args.package_manager, false).await?;
 
-  pub async fn uninstall() {}
+    let installed = if self.is_installed(&package_manager)? {
+      false
+    } else {
+      // 非交互模式下默认安装，设置 default 本来就需要这个版本
+      let confirmed = self.config.interaction.confirm(
+        format!(
+          "🤔 {}@{} is not installed, do you want to install it ?",
+          package_manager.name(),
+          package_manager.version()
+        ),
+        true,
+      )?;
 
-  pub async fn list() {}
+      if !confirmed {
+        bail!(
+          "{}@{} is not installed, installation was declined",
+          package_manager.name(),
+          package_manager.version()
+        );
+      }
 
-  pub async fn set_default() {}
+      self.download(&package_manager).await?;
+
+      true
+    };
+
+    let resolver = self.resolver();
+    let source_dir = resolver.package_manager_dir(&package_manager);
+    let default_dir = resolver.default_dir(&package_manager.kind());
+
+    if default_dir.symlink_metadata().is_ok() {
+      remove_default_link(&default_dir)?;
+    }
+
+    trace!(
+      r#"Creating symlink:
+{:?} -> {:?}"#,
+      &source_dir,
+      &default_dir
+    );
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&source_dir, &default_dir)?;
+
+    #[cfg(windows)]
+    std::os::windows::fs::symlink_dir(&source_dir, &default_dir)?;
+
+    self.print_action(
+      format!(
+        "🎉 {}@{} is now default",
+        package_manager.name(),
+        package_manager.version().bright_green()
+      ),
+      &package_manager,
+      installed,
+    )
+  }
+
+  pub async fn list(&self, args: PmListArgs) -> anyhow::Result<()> {
+    trace!(r#"Start show package manager list , args: {:#?}"#, args);
+
+    let kinds = match &args.kind {
+      Some(kind) => vec![kind.clone()],
+      None => KINDS.to_vec(),
+    };
+
+    let mut rows = vec![];
+
+    for kind in kinds {
+      let installed = self.resolver().installed_versions(&kind)?;
+
+      let versions = if args.remote {
+        let remote = RemoteVersions::fetch(self.config, &kind, args.refresh).await?;
+
+        let versions = remote
+          .versions
+          .into_iter()
+          .filter(|version| args.prerelease || version.pre.is_empty())
+          .map(|version| version.to_string())
+          .collect::<Vec<_>>();
+
+        let skip = args
+          .limit
+          .map_or(0, |limit| versions.len().saturating_sub(limit));
+
+        versions.into_iter().skip(skip).collect()
+      } else {
+        installed.clone()
+      };
+
+      for version in versions {
+        let package_manager = PackageManager::new(kind.clone(), version);
+        rows.push(PmVersionOutput {
+          name: kind.to_string(),
+          dir: self.resolver().package_manager_dir(&package_manager),
+          default: self.is_default(&package_manager),
+          installed: installed.contains(&package_manager.version().to_string()),
+          version: package_manager.version().to_string(),
+        });
+      }
+    }
+
+    if self.json {
+      println!("{}", serde_json::to_string_pretty(&rows)?);
+      return Ok(());
+    }
+
+    if rows.is_empty() {
+      if args.remote {
+        println!("😿 No remote package manager matches");
+      } else {
+        println!("😿 Local package manager list is empty");
+      }
+      return Ok(());
+    }
+
+    for row in rows {
+      println!(
+        "{:<2} {:<6} {:<16} {}",
+        if row.default { "->" } else { "" }.bright_green(),
+        row.name,
+        row.version.green(),
+        if args.remote && row.installed {
+          "✓"
+        } else {
+          ""
+        }
+        .bright_green()
+      );
+    }
+
+    Ok(())
+  }
 }
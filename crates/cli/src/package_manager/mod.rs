@@ -3,11 +3,11 @@ mod command_npm;
 mod command_pnpm;
 mod command_yarn;
 mod command_yarn_berry;
-// mod factory;
+mod factory;
 
 pub use command::*;
 pub use command_npm::*;
 pub use command_pnpm::*;
 pub use command_yarn::*;
 pub use command_yarn_berry::*;
-// pub use factory::*;
+pub use factory::*;
@@ -3,7 +3,7 @@ use serde::Serialize;
 
 use crate::package_manager::{InstallArgs, RemoveArgs, RunArgs};
 
-use super::manage_command::NodeManageCommands;
+use super::manage_command::{NodeManageCommands, PmManageCommands};
 
 #[derive(Subcommand, Debug, Serialize)]
 pub enum SnmCommands {
@@ -29,6 +29,15 @@ pub enum SnmCommands {
     command: NodeManageCommands,
   },
 
+  #[command(about = "Manage package manager versions.")]
+  Pm {
+    #[command(subcommand)]
+    command: PmManageCommands,
+  },
+
+  #[command(about = "Show how node or the package manager is resolved in the current directory.")]
+  Which(crate::which::WhichArgs),
+
   #[command(name = "setup", about = "Setup snm." , visible_aliases = ["st"])]
   SetUp,
   // #[command(name = "ai-commit", about = "Commit ai.")]
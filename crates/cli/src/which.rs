@@ -0,0 +1,240 @@
+use std::path::PathBuf;
+
+use colored::Colorize;
+use serde::Serialize;
+use snm_config::snm_config::SnmConfig;
+use snm_node::{NodeResolution, NodeResolver};
+use snm_package_manager::{
+  PackageManagerRequest, PackageManagerResolver, PackageManagerSource, ResolvedPackageManager,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WhichTool {
+  Node,
+  Npm,
+  Pnpm,
+  Yarn,
+}
+
+impl WhichTool {
+  fn as_str(&self) -> &'static str {
+    match self {
+      WhichTool::Node => "node",
+      WhichTool::Npm => "npm",
+      WhichTool::Pnpm => "pnpm",
+      WhichTool::Yarn => "yarn",
+    }
+  }
+}
+
+#[derive(Debug, clap::Args, Serialize)]
+pub struct WhichArgs {
+  #[arg(value_enum, help = "Tool to explain", default_value = "node")]
+  pub tool: WhichTool,
+}
+
+/// 包管理器的解析结果
+#[derive(Debug, Serialize)]
+struct PackageManagerResolution {
+  name: String,
+  /// 未在 package.json 中声明 packageManager 时为 `None`
+  version: Option<String>,
+  /// 声明了 packageManager 的 package.json
+  file: Option<PathBuf>,
+  /// 没有声明 packageManager 时，shim 直接使用 Node.js bin 目录或 PATH 中的同名命令
+  passthrough: bool,
+  /// 没有声明 packageManager 时使用的兜底，例如 `SNM_DEFAULT_PACKAGE_MANAGER=pnpm@9.x`
+  fallback: Option<String>,
+  bin: Option<PathBuf>,
+  installed: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct WhichReport<'a> {
+  tool: WhichTool,
+  node: NodeResolution,
+  package_manager: Option<PackageManagerResolution>,
+  strict: bool,
+  config: &'a SnmConfig,
+}
+
+pub async fn which(config: &SnmConfig, args: WhichArgs, json: bool) -> anyhow::Result<()> {
+  let node = NodeResolver::from(config.clone()).resolve().await?;
+
+  let package_manager = match args.tool {
+    WhichTool::Node => None,
+    tool => Some(resolve_package_manager(config, tool, &node).await?),
+  };
+
+  let report = WhichReport {
+    tool: args.tool,
+    node,
+    package_manager,
+    strict: config.strict,
+    config,
+  };
+
+  if json {
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    return Ok(());
+  }
+
+  print_report(&report)
+}
+
+async fn resolve_package_manager(
+  config: &SnmConfig,
+  tool: WhichTool,
+  node: &NodeResolution,
+) -> anyhow::Result<PackageManagerResolution> {
+  let resolver = PackageManagerResolver::from(config.clone());
+
+  let request = match resolver.find_request() {
+    Ok(request) => Some(request),
+    // 没有声明 packageManager 时错误来自兜底，需要说明原因，不能当作透传
+    Err(e) if !config.strict && !resolver.is_declared() => return Err(e),
+    Err(_) => None,
+  };
+
+  if let Some(PackageManagerRequest::Declared(package_manager, file)) = &request {
+    if package_manager.name() != tool.as_str() {
+      anyhow::bail!(
+        "Package manager mismatch, expect: {} ( from {:?} ), actual: {}",
+        package_manager.name().green(),
+        file,
+        tool.as_str().red()
+      );
+    }
+  }
+
+  // 兜底的包管理器只接管同名命令，其他命令透传，不会访问 registry
+  let request = match request {
+    Some(request)
+      if request
+        .kind()
+        .is_some_and(|kind| kind.as_ref() == tool.as_str()) =>
+    {
+      request
+    }
+    request => {
+      let bin = node.bin_dir.join(tool.as_str());
+      let installed = bin.try_exists()?;
+      let fallback = request
+        .filter(|request| request.kind().is_none() && tool == WhichTool::Npm)
+        .and_then(|request| request.source().fallback());
+      return Ok(PackageManagerResolution {
+        name: tool.as_str().to_string(),
+        version: None,
+        file: None,
+        passthrough: true,
+        fallback,
+        bin: installed.then_some(bin),
+        installed,
+      });
+    }
+  };
+
+  let ResolvedPackageManager {
+    package_manager: Some(package_manager),
+    source,
+  } = resolver.resolve_request(request).await?
+  else {
+    anyhow::bail!("Failed to resolve {}", tool.as_str());
+  };
+
+  let installed = resolver
+    .package_manager_dir(&package_manager)
+    .join("package.json")
+    .try_exists()?;
+
+  Ok(PackageManagerResolution {
+    name: package_manager.name().to_string(),
+    version: Some(package_manager.version().to_string()),
+    fallback: source.fallback(),
+    file: match source {
+      PackageManagerSource::PackageJson(file) => Some(file),
+      _ => None,
+    },
+    passthrough: false,
+    bin: resolver.bin_path(&package_manager, tool.as_str())?,
+    installed,
+  })
+}
+
+fn installed_label(installed: bool) -> String {
+  if installed {
+    "installed".bright_green().to_string()
+  } else {
+    "not installed".bright_red().to_string()
+  }
+}
+
+fn print_report(report: &WhichReport) -> anyhow::Result<()> {
+  let node = &report.node;
+
+  println!("{:<10} v{}", "node".bold(), node.version.bright_green());
+
+  match (&node.file, &node.source) {
+    (Some(file), Some(source)) => println!(
+      "{:<10} {} {:?} ( requested {:?} )",
+      "from",
+      source,
+      file,
+      node.requested.as_deref().unwrap_or_default()
+    ),
+    _ => println!(
+      "{:<10} default ( {:?} )",
+      "from",
+      report.config.node_bin_dir.join("default")
+    ),
+  }
+
+  println!(
+    "{:<10} {:?} ( {} )",
+    "binary",
+    node.exe,
+    installed_label(node.installed)
+  );
+
+  if let Some(pm) = &report.package_manager {
+    println!();
+
+    match &pm.version {
+      Some(version) => println!("{:<10} {}", pm.name.bold(), version.bright_green()),
+      None => println!("{:<10} {}", pm.name.bold(), "not pinned".bright_black()),
+    }
+
+    match (&pm.file, &pm.fallback) {
+      (Some(file), _) => println!("{:<10} packageManager {:?}", "from", file),
+      (None, Some(fallback)) => println!("{:<10} {}", "from", fallback),
+      (None, None) => println!("{:<10} Node.js bin dir or PATH", "from"),
+    }
+
+    match &pm.bin {
+      Some(bin) => println!(
+        "{:<10} {:?} ( {} )",
+        "binary",
+        bin,
+        installed_label(pm.installed)
+      ),
+      None => println!("{:<10} - ( {} )", "binary", installed_label(pm.installed)),
+    }
+  }
+
+  println!();
+  println!("{:<10} {}", "strict".bold(), report.strict);
+  println!("{}", "config".bold());
+
+  if let serde_json::Value::Object(map) = serde_json::to_value(report.config)? {
+    for (key, value) in map {
+      let value = match value {
+        serde_json::Value::String(s) => s,
+        value => value.to_string(),
+      };
+      println!("  {:<28} {}", key, value);
+    }
+  }
+
+  Ok(())
+}
@@ -0,0 +1,71 @@
+use std::{io::IsTerminal, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// shim 遇到未安装的 Node.js 或包管理器时的处理策略
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AutoInstall {
+  /// 直接下载安装
+  #[default]
+  Auto,
+  /// 安装前询问，stdin 不是 TTY 时按 `Never` 处理
+  Prompt,
+  /// 不安装，直接报错
+  Never,
+}
+
+impl AutoInstall {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      AutoInstall::Auto => "auto",
+      AutoInstall::Prompt => "prompt",
+      AutoInstall::Never => "never",
+    }
+  }
+
+  /// 结合当前终端得到实际生效的策略
+  pub fn effective(&self) -> AutoInstall {
+    match self {
+      AutoInstall::Prompt if !std::io::stdin().is_terminal() => AutoInstall::Never,
+      policy => *policy,
+    }
+  }
+}
+
+impl FromStr for AutoInstall {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim() {
+      "auto" => Ok(AutoInstall::Auto),
+      "prompt" => Ok(AutoInstall::Prompt),
+      "never" => Ok(AutoInstall::Never),
+      _ => anyhow::bail!(
+        "Unsupported auto install policy: {}, expected one of auto, prompt, never",
+        s
+      ),
+    }
+  }
+}
+
+impl std::fmt::Display for AutoInstall {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_parse_auto_install() -> anyhow::Result<()> {
+    assert_eq!("auto".parse::<AutoInstall>()?, AutoInstall::Auto);
+    assert_eq!("prompt".parse::<AutoInstall>()?, AutoInstall::Prompt);
+    assert_eq!(" never ".parse::<AutoInstall>()?, AutoInstall::Never);
+    assert!("always".parse::<AutoInstall>().is_err());
+    assert_eq!(AutoInstall::Never.effective(), AutoInstall::Never);
+    Ok(())
+  }
+}
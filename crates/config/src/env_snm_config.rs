@@ -7,15 +7,41 @@ pub struct EnvSnmConfig {
 
   pub node_dist_url: Option<String>,
 
+  pub node_dist_strategy: Option<String>,
+
+  pub node_shasums_url: Option<String>,
+
+  pub node_unofficial_dist_url: Option<String>,
+
   pub node_github_resource_host: Option<String>,
 
   pub node_white_list: Option<String>,
 
+  pub node_black_list: Option<String>,
+
   pub download_timeout_secs: Option<u64>,
 
   pub npm_registry: Option<String>,
 
   pub strict: Option<bool>,
+
+  pub node_version_sources: Option<String>,
+
+  pub metadata_cache_ttl_secs: Option<u64>,
+
+  pub offline: Option<bool>,
+
+  pub auto_install: Option<String>,
+
+  pub platform_os: Option<String>,
+
+  pub platform_arch: Option<String>,
+
+  pub platform_libc: Option<String>,
+
+  pub non_interactive: Option<bool>,
+
+  pub default_package_manager: Option<String>,
 }
 
 impl EnvSnmConfig {
@@ -41,12 +67,25 @@ mod tests {
   #[tokio::test]
   async fn should_parse_env_snm_config(ctx: &mut SnmTestContext) -> anyhow::Result<()> {
     let home_dir = ctx.get_temp_dir().to_string_lossy().to_string();
-    let node_dist_url = "https://nodejs.org/dist";
+    let node_dist_url = "https://npmmirror.com/mirrors/node,https://nodejs.org/dist";
+    let node_dist_strategy = "fastest";
+    let node_shasums_url = "https://nodejs.org/dist";
+    let node_unofficial_dist_url = "https://mirror.example.com/unofficial/release";
     let node_github_resource_host = "https://raw.githubusercontent.com";
     let node_white_list = "1.1.0,1.2.0";
+    let node_black_list = "<18";
     let download_timeout_secs = 60;
     let npm_registry = "https://test.npmjs.org";
     let strict = true;
+    let node_version_sources = "nvmrc,node-version";
+    let metadata_cache_ttl_secs = 600;
+    let offline = true;
+    let auto_install = "prompt";
+    let platform_os = "linux";
+    let platform_arch = "arm64";
+    let platform_libc = "musl";
+    let non_interactive = true;
+    let default_package_manager = "pnpm@9.x";
 
     let envs = [
       (format!("{}_HOME_DIR", ctx.get_id()), home_dir.clone()),
@@ -54,6 +93,18 @@ mod tests {
         format!("{}_NODE_DIST_URL", ctx.get_id()),
         node_dist_url.to_string(),
       ),
+      (
+        format!("{}_NODE_DIST_STRATEGY", ctx.get_id()),
+        node_dist_strategy.to_string(),
+      ),
+      (
+        format!("{}_NODE_SHASUMS_URL", ctx.get_id()),
+        node_shasums_url.to_string(),
+      ),
+      (
+        format!("{}_NODE_UNOFFICIAL_DIST_URL", ctx.get_id()),
+        node_unofficial_dist_url.to_string(),
+      ),
       (
         format!("{}_NODE_GITHUB_RESOURCE_HOST", ctx.get_id()),
         node_github_resource_host.to_string(),
@@ -62,6 +113,10 @@ mod tests {
         format!("{}_NODE_WHITE_LIST", ctx.get_id()),
         node_white_list.to_string(),
       ),
+      (
+        format!("{}_NODE_BLACK_LIST", ctx.get_id()),
+        node_black_list.to_string(),
+      ),
       (
         format!("{}_DOWNLOAD_TIMEOUT_SECS", ctx.get_id()),
         download_timeout_secs.to_string(),
@@ -71,6 +126,39 @@ mod tests {
         npm_registry.to_string(),
       ),
       (format!("{}_STRICT", ctx.get_id()), strict.to_string()),
+      (
+        format!("{}_NODE_VERSION_SOURCES", ctx.get_id()),
+        node_version_sources.to_string(),
+      ),
+      (
+        format!("{}_METADATA_CACHE_TTL_SECS", ctx.get_id()),
+        metadata_cache_ttl_secs.to_string(),
+      ),
+      (format!("{}_OFFLINE", ctx.get_id()), offline.to_string()),
+      (
+        format!("{}_AUTO_INSTALL", ctx.get_id()),
+        auto_install.to_string(),
+      ),
+      (
+        format!("{}_PLATFORM_OS", ctx.get_id()),
+        platform_os.to_string(),
+      ),
+      (
+        format!("{}_PLATFORM_ARCH", ctx.get_id()),
+        platform_arch.to_string(),
+      ),
+      (
+        format!("{}_PLATFORM_LIBC", ctx.get_id()),
+        platform_libc.to_string(),
+      ),
+      (
+        format!("{}_NON_INTERACTIVE", ctx.get_id()),
+        non_interactive.to_string(),
+      ),
+      (
+        format!("{}_DEFAULT_PACKAGE_MANAGER", ctx.get_id()),
+        default_package_manager.to_string(),
+      ),
     ];
 
     ctx.set_envs(&envs);
@@ -79,14 +167,42 @@ mod tests {
 
     assert_eq!(config.home_dir, Some(home_dir.clone()));
     assert_eq!(config.node_dist_url, Some(node_dist_url.to_string()));
+    assert_eq!(
+      config.node_dist_strategy,
+      Some(node_dist_strategy.to_string())
+    );
+    assert_eq!(config.node_shasums_url, Some(node_shasums_url.to_string()));
+    assert_eq!(
+      config.node_unofficial_dist_url,
+      Some(node_unofficial_dist_url.to_string())
+    );
     assert_eq!(
       config.node_github_resource_host,
       Some(node_github_resource_host.to_string())
     );
     assert_eq!(config.node_white_list, Some(node_white_list.to_string()));
+    assert_eq!(config.node_black_list, Some(node_black_list.to_string()));
     assert_eq!(config.download_timeout_secs, Some(download_timeout_secs));
     assert_eq!(config.npm_registry, Some(npm_registry.to_string()));
     assert_eq!(config.strict, Some(strict));
+    assert_eq!(
+      config.node_version_sources,
+      Some(node_version_sources.to_string())
+    );
+    assert_eq!(
+      config.metadata_cache_ttl_secs,
+      Some(metadata_cache_ttl_secs)
+    );
+    assert_eq!(config.offline, Some(offline));
+    assert_eq!(config.auto_install, Some(auto_install.to_string()));
+    assert_eq!(config.platform_os, Some(platform_os.to_string()));
+    assert_eq!(config.platform_arch, Some(platform_arch.to_string()));
+    assert_eq!(config.platform_libc, Some(platform_libc.to_string()));
+    assert_eq!(config.non_interactive, Some(non_interactive));
+    assert_eq!(
+      config.default_package_manager,
+      Some(default_package_manager.to_string())
+    );
 
     Ok(())
   }
@@ -0,0 +1,78 @@
+use std::io::IsTerminal;
+
+use dialoguer::Confirm;
+use serde::{Deserialize, Serialize};
+
+/// 需要用户确认的操作如何得到答案
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Interaction {
+  /// 在终端中询问，stdin 不是 TTY 时使用默认答案
+  #[default]
+  Prompt,
+  /// `--yes`，所有确认都回答是
+  Yes,
+  /// `--no`，所有确认都回答否
+  No,
+  /// `SNM_NON_INTERACTIVE` 或 CI 环境，所有确认都使用各自的默认答案
+  Defaults,
+}
+
+impl Interaction {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Interaction::Prompt => "prompt",
+      Interaction::Yes => "yes",
+      Interaction::No => "no",
+      Interaction::Defaults => "defaults",
+    }
+  }
+
+  /// 询问用户，非交互模式下直接返回答案，`default` 是该提示文档中约定的默认答案
+  pub fn confirm(&self, prompt: String, default: bool) -> anyhow::Result<bool> {
+    match self {
+      Interaction::Yes => Ok(true),
+      Interaction::No => Ok(false),
+      Interaction::Defaults => Ok(default),
+      Interaction::Prompt if !std::io::stdin().is_terminal() => Ok(default),
+      Interaction::Prompt => Ok(
+        Confirm::new()
+          .with_prompt(prompt)
+          .default(default)
+          .interact()?,
+      ),
+    }
+  }
+}
+
+impl std::fmt::Display for Interaction {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+/// 常见 CI 服务都会设置 `CI`，值为 `false` 或 `0` 时视为未设置
+pub fn is_ci() -> bool {
+  std::env::var("CI")
+    .map(|value| !matches!(value.trim(), "" | "0" | "false" | "FALSE" | "False"))
+    .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_answer_without_prompt() -> anyhow::Result<()> {
+    for default in [true, false] {
+      assert!(Interaction::Yes.confirm(String::from("?"), default)?);
+      assert!(!Interaction::No.confirm(String::from("?"), default)?);
+      assert_eq!(
+        Interaction::Defaults.confirm(String::from("?"), default)?,
+        default
+      );
+    }
+
+    Ok(())
+  }
+}
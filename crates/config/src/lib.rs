@@ -1,3 +1,7 @@
+pub mod auto_install;
 pub mod env_snm_config;
-mod platform;
+pub mod interaction;
+pub mod mirror_strategy;
+pub mod node_version_source;
+pub mod platform;
 pub mod snm_config;
@@ -0,0 +1,44 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// 配置了多个 Node.js 镜像时的选择方式
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MirrorStrategy {
+  /// 按配置顺序依次尝试，失败后换下一个
+  #[default]
+  Fallback,
+  /// 先探测各镜像的响应速度，从最快的开始尝试，失败后同样换下一个
+  Fastest,
+}
+
+impl MirrorStrategy {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      MirrorStrategy::Fallback => "fallback",
+      MirrorStrategy::Fastest => "fastest",
+    }
+  }
+}
+
+impl FromStr for MirrorStrategy {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim() {
+      "fallback" => Ok(MirrorStrategy::Fallback),
+      "fastest" => Ok(MirrorStrategy::Fastest),
+      _ => anyhow::bail!(
+        "Unsupported mirror strategy: {}, expected one of fallback, fastest",
+        s
+      ),
+    }
+  }
+}
+
+impl std::fmt::Display for MirrorStrategy {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
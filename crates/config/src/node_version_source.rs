@@ -0,0 +1,115 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// 可以声明项目 Node.js 版本的来源
+///
+/// 默认优先级与枚举顺序一致：`.node-version` > `.nvmrc` > `.tool-versions` > `volta.node` > `engines.node`
+///
+/// package.json 中的来源只在所有层级都找不到版本文件时才会读取，
+/// 避免 monorepo 子包或 `node_modules/<dep>` 的 `engines` 覆盖根目录的 `.node-version`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NodeVersionSource {
+  /// `.node-version`
+  NodeVersion,
+  /// `.nvmrc`
+  Nvmrc,
+  /// `.tool-versions` 中的 `nodejs` 行
+  ToolVersions,
+  /// package.json 中的 `volta.node`
+  Volta,
+  /// package.json 中的 `engines.node`
+  Engines,
+}
+
+impl NodeVersionSource {
+  pub const DEFAULT_PRECEDENCE: [NodeVersionSource; 5] = [
+    NodeVersionSource::NodeVersion,
+    NodeVersionSource::Nvmrc,
+    NodeVersionSource::ToolVersions,
+    NodeVersionSource::Volta,
+    NodeVersionSource::Engines,
+  ];
+
+  pub fn file_name(&self) -> &'static str {
+    match self {
+      NodeVersionSource::NodeVersion => ".node-version",
+      NodeVersionSource::Nvmrc => ".nvmrc",
+      NodeVersionSource::ToolVersions => ".tool-versions",
+      NodeVersionSource::Volta | NodeVersionSource::Engines => "package.json",
+    }
+  }
+
+  /// 是否来自 package.json
+  pub fn is_package_json(&self) -> bool {
+    matches!(self, NodeVersionSource::Volta | NodeVersionSource::Engines)
+  }
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      NodeVersionSource::NodeVersion => "node-version",
+      NodeVersionSource::Nvmrc => "nvmrc",
+      NodeVersionSource::ToolVersions => "tool-versions",
+      NodeVersionSource::Volta => "volta",
+      NodeVersionSource::Engines => "engines",
+    }
+  }
+
+  /// 解析逗号分隔的来源列表，例如 `node-version,nvmrc,engines`
+  pub fn parse_list(raw: &str) -> anyhow::Result<Vec<Self>> {
+    raw
+      .split(',')
+      .map(|item| item.trim())
+      .filter(|item| !item.is_empty())
+      .map(Self::from_str)
+      .collect()
+  }
+}
+
+impl FromStr for NodeVersionSource {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim_start_matches('.') {
+      "node-version" => Ok(NodeVersionSource::NodeVersion),
+      "nvmrc" => Ok(NodeVersionSource::Nvmrc),
+      "tool-versions" => Ok(NodeVersionSource::ToolVersions),
+      "volta" => Ok(NodeVersionSource::Volta),
+      "engines" => Ok(NodeVersionSource::Engines),
+      _ => anyhow::bail!(
+        "Unsupported node version source: {}, expected one of node-version, nvmrc, tool-versions, volta, engines",
+        s
+      ),
+    }
+  }
+}
+
+impl std::fmt::Display for NodeVersionSource {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_parse_source_list() -> anyhow::Result<()> {
+    let sources = NodeVersionSource::parse_list("nvmrc, .node-version,engines")?;
+
+    assert_eq!(
+      sources,
+      vec![
+        NodeVersionSource::Nvmrc,
+        NodeVersionSource::NodeVersion,
+        NodeVersionSource::Engines
+      ]
+    );
+
+    assert!(NodeVersionSource::parse_list("nvmrc,foo").is_err());
+
+    Ok(())
+  }
+}
@@ -1,3 +1,5 @@
+use std::sync::OnceLock;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -5,8 +7,126 @@ pub struct Platform {
   pub os: String,
   pub arch: String,
   pub ext: String,
+  /// 非默认 glibc 的 Linux，`musl` 或 `glibc-217`，这两种只有 unofficial-builds 提供
+  ///
+  /// 运行时只检测 musl，`glibc-217` 由 `for_version` 按 Node.js 版本决定
+  pub libc: Option<String>,
 }
 
+impl Platform {
+  /// 产物名中的平台部分，例如 `linux-x64`、`linux-x64-musl`
+  pub fn target(&self) -> String {
+    match &self.libc {
+      Some(libc) => format!("{}-{}-{}", self.os, self.arch, libc),
+      None => format!("{}-{}", self.os, self.arch),
+    }
+  }
+
+  /// nodejs.org 不提供的产物需要从 unofficial-builds 下载
+  pub fn is_unofficial(&self) -> bool {
+    self.libc.is_some() || matches!(self.arch.as_str(), "riscv64" | "loong64")
+  }
+
+  /// 是否是当前机器的平台，只有本机平台的 Node.js 能被 shim 执行
+  pub fn is_native(&self) -> bool {
+    self.os == os() && self.arch == arch() && self.libc.as_deref() == libc()
+  }
+
+  /// 下载某个 Node.js 版本时使用的平台
+  ///
+  /// 官方 Linux 产物从 Node.js 18 起要求 glibc >= 2.28，更老的系统上 x64 的 18 及以上版本改用 glibc-217 构建，
+  /// 只在这里检测 glibc，避免每次 shim 调用都去检测
+  pub fn for_version(&self, version: &str) -> Self {
+    let major = version
+      .trim()
+      .trim_start_matches('v')
+      .split('.')
+      .next()
+      .and_then(|major| major.parse::<u64>().ok());
+
+    if self.libc.is_none()
+      && self.is_native()
+      && needs_glibc_217(&self.arch, major, glibc_version())
+    {
+      return Self {
+        libc: Some(String::from("glibc-217")),
+        ..self.clone()
+      };
+    }
+
+    self.clone()
+  }
+
+  /// 用 `--os` / `--arch` / `--libc` 或 `SNM_PLATFORM_OS` / `SNM_PLATFORM_ARCH` / `SNM_PLATFORM_LIBC` 覆盖平台，
+  /// 用于给其他机器准备 Node.js，例如在 macOS 上为 Alpine 镜像准备 musl 构建
+  pub fn with_override(
+    &self,
+    os: Option<&str>,
+    arch: Option<&str>,
+    libc: Option<&str>,
+  ) -> anyhow::Result<Self> {
+    if os.is_none() && arch.is_none() && libc.is_none() {
+      return Ok(self.clone());
+    }
+
+    let os = os.map(|os| os.trim()).unwrap_or(&self.os);
+    let arch = arch.map(|arch| arch.trim()).unwrap_or(&self.arch);
+    let libc = libc.map(|libc| libc.trim());
+
+    if !SUPPORTED_OS.contains(&os) {
+      anyhow::bail!(
+        "Unsupported os: {}, expected one of {}",
+        os,
+        SUPPORTED_OS.join(", ")
+      );
+    }
+
+    if !SUPPORTED_ARCH.contains(&arch) {
+      anyhow::bail!(
+        "Unsupported arch: {}, expected one of {}",
+        arch,
+        SUPPORTED_ARCH.join(", ")
+      );
+    }
+
+    let mut platform = Self {
+      os: os.to_string(),
+      arch: arch.to_string(),
+      ext: if os == "win" { "zip" } else { "tar.xz" }.to_string(),
+      libc: None,
+    };
+
+    platform.libc = match libc {
+      Some(libc) if !SUPPORTED_LIBC.contains(&libc) => anyhow::bail!(
+        "Unsupported libc: {}, expected one of {}",
+        libc,
+        SUPPORTED_LIBC.join(", ")
+      ),
+      Some(libc) if platform.os != "linux" => anyhow::bail!(
+        "libc {} is only supported for linux, but the target os is {}",
+        libc,
+        platform.os
+      ),
+      // 官方构建，不带 libc 后缀
+      Some("glibc") => None,
+      Some(libc) => Some(libc.to_string()),
+      // 只有目标就是本机时才沿用运行时检测到的 libc
+      None if platform.os == self.os && platform.arch == self.arch => self.libc.clone(),
+      None => None,
+    };
+
+    Ok(platform)
+  }
+}
+
+const SUPPORTED_OS: &[&str] = &["darwin", "linux", "win", "aix"];
+
+const SUPPORTED_LIBC: &[&str] = &["glibc", "musl", "glibc-217"];
+
+const SUPPORTED_ARCH: &[&str] = &[
+  "x64", "x86", "arm64", "armv7l", "ppc64", "ppc64le", "s390x", "riscv64", "loong64",
+];
+
 fn os() -> &'static str {
   #[cfg(target_os = "macos")]
   {
@@ -39,7 +159,31 @@ fn arch() -> &'static str {
   {
     "armv7l"
   }
-  #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "arm")))]
+  #[cfg(all(target_arch = "powerpc64", target_endian = "little"))]
+  {
+    "ppc64le"
+  }
+  #[cfg(target_arch = "s390x")]
+  {
+    "s390x"
+  }
+  #[cfg(target_arch = "riscv64")]
+  {
+    "riscv64"
+  }
+  #[cfg(target_arch = "loongarch64")]
+  {
+    "loong64"
+  }
+  #[cfg(not(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "arm",
+    all(target_arch = "powerpc64", target_endian = "little"),
+    target_arch = "s390x",
+    target_arch = "riscv64",
+    target_arch = "loongarch64"
+  )))]
   {
     "unknown"
   }
@@ -56,12 +200,177 @@ fn ext() -> &'static str {
   }
 }
 
+/// 官方 Linux 产物要求 glibc >= 2.28，更老的系统只能使用 glibc-217 构建
+fn is_legacy_glibc(version: &str) -> bool {
+  let mut parts = version.trim().split('.').map(|part| part.parse::<u32>());
+
+  match (parts.next(), parts.next()) {
+    (Some(Ok(major)), Some(Ok(minor))) => (major, minor) < (2, 28),
+    _ => false,
+  }
+}
+
+/// glibc-217 构建只有 x64，且 Node.js 18 之前的官方产物本身就支持 glibc 2.17
+fn needs_glibc_217(arch: &str, major: Option<u64>, glibc: Option<&str>) -> bool {
+  arch == "x64" && major.is_some_and(|major| major >= 18) && glibc.is_some_and(is_legacy_glibc)
+}
+
+/// 宿主的 glibc 版本，同一进程内只检测一次
+fn glibc_version() -> Option<&'static str> {
+  static GLIBC_VERSION: OnceLock<Option<String>> = OnceLock::new();
+
+  GLIBC_VERSION.get_or_init(detect_glibc_version).as_deref()
+}
+
+#[cfg(target_os = "linux")]
+fn detect_glibc_version() -> Option<String> {
+  #[cfg(target_env = "gnu")]
+  {
+    extern "C" {
+      fn gnu_get_libc_version() -> *const std::os::raw::c_char;
+    }
+
+    // SAFETY: gnu_get_libc_version 返回 glibc 内部的静态字符串
+    let version = unsafe { std::ffi::CStr::from_ptr(gnu_get_libc_version()) };
+
+    Some(version.to_string_lossy().into_owned())
+  }
+
+  #[cfg(not(target_env = "gnu"))]
+  {
+    // 静态链接的 snm 拿不到宿主的 glibc 版本，只能看 `ldd --version` 的第一行，例如 `ldd (GNU libc) 2.17`
+    let output = std::process::Command::new("ldd")
+      .arg("--version")
+      .output()
+      .ok()?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    stdout
+      .lines()
+      .next()?
+      .split_whitespace()
+      .last()
+      .map(|version| version.to_string())
+  }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn detect_glibc_version() -> Option<String> {
+  None
+}
+
+/// 本机的 libc，同一进程内只检测一次
+fn libc() -> Option<&'static str> {
+  static LIBC: OnceLock<Option<String>> = OnceLock::new();
+
+  LIBC.get_or_init(detect_libc).as_deref()
+}
+
+#[cfg(target_os = "linux")]
+fn detect_libc() -> Option<String> {
+  // Alpine 等 musl 系统上存在 /lib/ld-musl-<arch>.so.1
+  let is_musl = std::fs::read_dir("/lib")
+    .map(|entries| {
+      entries
+        .filter_map(|entry| entry.ok())
+        .any(|entry| entry.file_name().to_string_lossy().starts_with("ld-musl-"))
+    })
+    .unwrap_or(false);
+
+  is_musl.then(|| String::from("musl"))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn detect_libc() -> Option<String> {
+  None
+}
+
 impl Default for Platform {
   fn default() -> Self {
     Self {
       os: os().to_string(),
       arch: arch().to_string(),
       ext: ext().to_string(),
+      libc: libc().map(String::from),
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_detect_legacy_glibc() {
+    assert!(is_legacy_glibc("2.17"));
+    assert!(is_legacy_glibc("2.27"));
+    assert!(!is_legacy_glibc("2.28"));
+    assert!(!is_legacy_glibc("2.39"));
+    assert!(!is_legacy_glibc("unknown"));
+  }
+
+  #[test]
+  fn should_use_glibc_217_only_for_x64_node_18_and_later() {
+    assert!(needs_glibc_217("x64", Some(18), Some("2.17")));
+    assert!(needs_glibc_217("x64", Some(22), Some("2.27")));
+    assert!(!needs_glibc_217("x64", Some(16), Some("2.17")));
+    assert!(!needs_glibc_217("arm64", Some(20), Some("2.17")));
+    assert!(!needs_glibc_217("x64", Some(20), Some("2.28")));
+    assert!(!needs_glibc_217("x64", None, Some("2.17")));
+    assert!(!needs_glibc_217("x64", Some(20), None));
+  }
+
+  #[test]
+  fn should_override_platform() -> anyhow::Result<()> {
+    let native = Platform::default();
+
+    let platform = native.with_override(Some("win"), Some("arm64"), None)?;
+    assert_eq!(platform.target(), "win-arm64");
+    assert_eq!(platform.ext, "zip");
+    assert!(platform.libc.is_none());
+
+    let platform = native.with_override(None, None, None)?;
+    assert_eq!(platform.target(), native.target());
+    assert!(platform.is_native());
+
+    assert!(native.with_override(Some("plan9"), None, None).is_err());
+    assert!(native.with_override(None, Some("mips"), None).is_err());
+
+    let platform = native.with_override(Some("linux"), Some("x64"), Some("musl"))?;
+    assert_eq!(platform.target(), "linux-x64-musl");
+    assert!(platform.is_unofficial());
+
+    let platform = native.with_override(Some("linux"), Some("arm64"), Some("glibc"))?;
+    assert_eq!(platform.target(), "linux-arm64");
+
+    assert!(native
+      .with_override(Some("darwin"), Some("arm64"), Some("musl"))
+      .is_err());
+    assert!(native
+      .with_override(Some("linux"), None, Some("uclibc"))
+      .is_err());
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_build_target() {
+    let platform = Platform {
+      os: String::from("linux"),
+      arch: String::from("x64"),
+      ext: String::from("tar.xz"),
+      libc: Some(String::from("musl")),
+    };
+    assert_eq!(platform.target(), "linux-x64-musl");
+    assert!(platform.is_unofficial());
+
+    let platform = Platform {
+      libc: None,
+      arch: String::from("riscv64"),
+      ..platform
+    };
+    assert_eq!(platform.target(), "linux-riscv64");
+    assert!(platform.is_unofficial());
+  }
+}
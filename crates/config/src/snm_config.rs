@@ -8,24 +8,109 @@ use serde::{Deserialize, Serialize};
 use snm_npmrc::NpmrcReader;
 use snm_utils::consts::SNM_PREFIX;
 
-use crate::{env_snm_config::EnvSnmConfig, platform::Platform};
+use crate::{
+  auto_install::AutoInstall,
+  env_snm_config::EnvSnmConfig,
+  interaction::{is_ci, Interaction},
+  mirror_strategy::MirrorStrategy,
+  node_version_source::NodeVersionSource,
+  platform::Platform,
+};
 
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct SnmConfig {
   pub node_bin_dir: PathBuf,
+  /// 非本机平台的 Node.js 安装在 `<node_target_dir>/<target>/<version>`，不和本机的混在一起
+  pub node_target_dir: PathBuf,
+  /// `snm node alias` 创建的别名，每个别名是一个指向 node_bin_dir 下具体版本的软链
+  pub node_alias_dir: PathBuf,
   pub download_dir: PathBuf,
   pub node_modules_dir: PathBuf,
-  pub node_dist_url: String,
+  /// index.json、schedule.json 等远程元数据的缓存目录
+  pub cache_dir: PathBuf,
+  /// shim 记录的各 Node.js 版本使用情况，`snm node prune` 据此清理
+  pub node_usage_file: PathBuf,
+  /// Node.js 镜像列表，按顺序尝试，来自逗号分隔的 `SNM_NODE_DIST_URL`
+  pub node_dist_urls: Vec<String>,
+  pub node_dist_strategy: MirrorStrategy,
+  /// 可信的 SHASUMS256.txt 来源，未配置时从下载归档的同一个镜像获取
+  pub node_shasums_url: Option<String>,
+  /// unofficial-builds 的地址，musl、glibc-217 以及 riscv64、loong64 的产物从这里下载
+  pub node_unofficial_dist_url: String,
   pub node_github_resource_host: String,
+  /// 允许使用的 Node.js 版本，逗号分隔的精确版本或 semver 范围，为空时不限制
   pub node_white_list: String,
+  /// 禁止使用的 Node.js 版本，格式同 `node_white_list`，优先级高于白名单
+  pub node_black_list: String,
   pub download_timeout_secs: u64,
   pub npm_registry: String,
   pub workspace: PathBuf,
   pub strict: bool,
   pub platform: Platform,
+  /// 查找项目 Node.js 版本时依次尝试的来源，同一目录下靠前的优先
+  pub node_version_sources: Vec<NodeVersionSource>,
+  /// 远程元数据缓存的有效期，过期后会带上 ETag / Last-Modified 重新校验
+  pub metadata_cache_ttl_secs: u64,
+  /// 离线模式，只使用已安装的工具链和本地缓存，不发起任何网络请求
+  pub offline: bool,
+  /// shim 遇到未安装的 Node.js 或包管理器时是否自动安装
+  pub auto_install: AutoInstall,
+  /// 运行在 CI 中（设置了 `CI` 环境变量），`snm install` 强制使用 frozen lockfile，输出不带颜色
+  pub ci: bool,
+  /// 确认提示的应答方式，`SNM_NON_INTERACTIVE` 或 CI 中使用默认答案，命令行的 `--yes` / `--no` 会覆盖它
+  pub interaction: Interaction,
+  /// 非严格模式下 package.json 没有声明 packageManager 时使用的包管理器，例如 `pnpm@9.1.0`、`pnpm@9.x`、`pnpm`
+  pub default_package_manager: Option<String>,
 }
 
 impl SnmConfig {
+  /// 某个版本的 Node.js 安装目录，本机平台在 node_bin_dir 下，其他平台按 target 区分
+  pub fn node_home_dir(&self, version: &str) -> PathBuf {
+    if self.platform.is_native() {
+      self.node_bin_dir.join(version)
+    } else {
+      self
+        .node_target_dir
+        .join(self.platform.target())
+        .join(version)
+    }
+  }
+
+  /// shim 需要安装 `subject`（例如 `Node.js v20.11.0`、`pnpm@9.1.0`）时按 `auto_install` 策略确认，
+  /// 不允许安装时报错并提示先执行 `install_command`
+  pub fn check_auto_install(&self, subject: &str, install_command: &str) -> anyhow::Result<()> {
+    let policy = self.auto_install;
+
+    match policy.effective() {
+      AutoInstall::Auto => Ok(()),
+      AutoInstall::Prompt => {
+        // 非交互模式下默认不安装
+        let confirmed = self.interaction.confirm(
+          format!(
+            "🤔 {} is not installed, do you want to install it ?",
+            subject
+          ),
+          false,
+        )?;
+        if !confirmed {
+          anyhow::bail!("{} is not installed, installation was declined", subject);
+        }
+        Ok(())
+      }
+      AutoInstall::Never => anyhow::bail!(
+        "{} is not installed and auto install is disabled ( SNM_AUTO_INSTALL={}{} ), run `{}` first",
+        subject,
+        policy,
+        if policy == AutoInstall::Prompt {
+          ", stdin is not a TTY"
+        } else {
+          ""
+        },
+        install_command
+      ),
+    }
+  }
+
   pub fn try_default() -> anyhow::Result<Self> {
     Self::from(SNM_PREFIX, current_dir()?)
   }
@@ -46,18 +131,54 @@ impl SnmConfig {
       .join(".snm");
 
     let node_bin_dir = base_dir.join(String::from("node_bin"));
+    let node_target_dir = base_dir.join(String::from("node_targets"));
+    let node_alias_dir = base_dir.join(String::from("node_alias"));
     let download_dir = base_dir.join(String::from("downloads"));
     let node_modules_dir = base_dir.join(String::from("node_modules"));
+    let cache_dir = base_dir.join(String::from("cache"));
+    let node_usage_file = base_dir.join(String::from("node_usage.json"));
 
-    for dir in [&base_dir, &node_bin_dir, &download_dir, &node_modules_dir] {
+    for dir in [
+      &base_dir,
+      &node_bin_dir,
+      &node_alias_dir,
+      &download_dir,
+      &node_modules_dir,
+      &cache_dir,
+    ] {
       if !dir.try_exists()? {
         fs::create_dir_all(dir)?;
       }
     }
 
-    let node_dist_url = config
+    let node_dist_urls = config
       .node_dist_url
-      .unwrap_or(String::from("https://nodejs.org/dist"));
+      .unwrap_or(String::from("https://nodejs.org/dist"))
+      .split(',')
+      .map(|url| url.trim().trim_end_matches('/').to_string())
+      .filter(|url| !url.is_empty())
+      .collect::<Vec<_>>();
+
+    if node_dist_urls.is_empty() {
+      anyhow::bail!("SNM_NODE_DIST_URL must contain at least one URL");
+    }
+
+    let node_dist_strategy = match config.node_dist_strategy {
+      Some(raw) => raw.parse::<MirrorStrategy>()?,
+      None => MirrorStrategy::default(),
+    };
+
+    let node_shasums_url = config
+      .node_shasums_url
+      .map(|url| url.trim().trim_end_matches('/').to_string())
+      .filter(|url| !url.is_empty());
+
+    let node_unofficial_dist_url = config
+      .node_unofficial_dist_url
+      .map(|url| url.trim().trim_end_matches('/').to_string())
+      .unwrap_or(String::from(
+        "https://unofficial-builds.nodejs.org/download/release",
+      ));
 
     let node_github_resource_host = config
       .node_github_resource_host
@@ -65,20 +186,62 @@ impl SnmConfig {
 
     let node_white_list = config.node_white_list.unwrap_or(String::from(""));
 
+    let node_black_list = config.node_black_list.unwrap_or(String::from(""));
+
     let strict = config.strict.unwrap_or(false);
 
+    let auto_install = match config.auto_install {
+      Some(raw) => raw.parse::<AutoInstall>()?,
+      None => AutoInstall::default(),
+    };
+
+    let ci = is_ci();
+
+    let interaction = if config.non_interactive.unwrap_or(ci) {
+      Interaction::Defaults
+    } else {
+      Interaction::Prompt
+    };
+
+    let node_version_sources = match config.node_version_sources {
+      Some(raw) => NodeVersionSource::parse_list(&raw)?,
+      None => NodeVersionSource::DEFAULT_PRECEDENCE.to_vec(),
+    };
+
     Ok(Self {
       workspace: workspace.as_ref().to_path_buf(),
       node_bin_dir: node_bin_dir,
+      node_target_dir,
+      node_alias_dir,
       download_dir: download_dir,
       node_modules_dir: node_modules_dir,
-      node_dist_url: node_dist_url,
+      cache_dir,
+      node_usage_file,
+      node_dist_urls,
+      node_dist_strategy,
+      node_shasums_url,
+      node_unofficial_dist_url,
       node_github_resource_host: node_github_resource_host,
       node_white_list: node_white_list,
+      node_black_list,
       download_timeout_secs: config.download_timeout_secs.unwrap_or(30),
       npm_registry: npm_registry,
       strict: strict,
-      platform: Platform::default(),
+      platform: Platform::default().with_override(
+        config.platform_os.as_deref(),
+        config.platform_arch.as_deref(),
+        config.platform_libc.as_deref(),
+      )?,
+      node_version_sources,
+      metadata_cache_ttl_secs: config.metadata_cache_ttl_secs.unwrap_or(3600),
+      offline: config.offline.unwrap_or(false),
+      auto_install,
+      ci,
+      interaction,
+      default_package_manager: config
+        .default_package_manager
+        .map(|raw| raw.trim().to_string())
+        .filter(|raw| !raw.is_empty()),
     })
   }
 }
@@ -104,6 +267,19 @@ mod tests {
       config.node_bin_dir,
       ctx.get_temp_dir().join(".snm/node_bin")
     );
+    assert_eq!(
+      config.node_target_dir,
+      ctx.get_temp_dir().join(".snm/node_targets")
+    );
+    assert!(config.platform.is_native());
+    assert_eq!(
+      config.node_home_dir("20.11.0"),
+      ctx.get_temp_dir().join(".snm/node_bin/20.11.0")
+    );
+    assert_eq!(
+      config.node_alias_dir,
+      ctx.get_temp_dir().join(".snm/node_alias")
+    );
     assert_eq!(
       config.download_dir,
       ctx.get_temp_dir().join(".snm/downloads")
@@ -112,15 +288,37 @@ mod tests {
       config.node_modules_dir,
       ctx.get_temp_dir().join(".snm/node_modules")
     );
-    assert_eq!(config.node_dist_url, "https://nodejs.org/dist");
+    assert_eq!(config.cache_dir, ctx.get_temp_dir().join(".snm/cache"));
+    assert_eq!(
+      config.node_usage_file,
+      ctx.get_temp_dir().join(".snm/node_usage.json")
+    );
+    assert_eq!(config.node_dist_urls, vec!["https://nodejs.org/dist"]);
+    assert_eq!(config.node_dist_strategy, MirrorStrategy::Fallback);
+    assert_eq!(config.node_shasums_url, None);
+    assert_eq!(
+      config.node_unofficial_dist_url,
+      "https://unofficial-builds.nodejs.org/download/release"
+    );
     assert_eq!(
       config.node_github_resource_host,
       "https://raw.githubusercontent.com"
     );
     assert_eq!(config.node_white_list, "");
+    assert_eq!(config.node_black_list, "");
     assert_eq!(config.download_timeout_secs, 30);
     assert_eq!(config.npm_registry, "https://registry.npmjs.org");
     assert_eq!(config.strict, false);
+    assert_eq!(
+      config.node_version_sources,
+      NodeVersionSource::DEFAULT_PRECEDENCE.to_vec()
+    );
+    assert_eq!(config.metadata_cache_ttl_secs, 3600);
+    assert!(!config.offline);
+    assert_eq!(config.auto_install, AutoInstall::Auto);
+    // 未设置 SNM_NON_INTERACTIVE 时跟随 CI 检测
+    assert_eq!(config.interaction == Interaction::Defaults, config.ci);
+    assert_eq!(config.default_package_manager, None);
 
     Ok(())
   }
@@ -1,17 +1,64 @@
-use robust_downloader::RobustDownloader;
-use std::path::PathBuf;
-use tarball::ArchiveExtension;
+use anyhow::bail;
+use robust_downloader::{DownloadItem, RobustDownloader};
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+use tracing::trace;
 
+mod metadata_cache;
+mod node_channel;
+mod pinned_digest;
 mod resource;
 mod resource_node;
 mod resource_package_manager;
 mod tarball;
 
+pub use metadata_cache::MetadataCache;
+pub use node_channel::NodeChannel;
+pub use pinned_digest::PinnedDigest;
 pub use resource::DownloadResource;
 pub use resource_node::DownloadNodeResource;
 pub use resource_package_manager::DownloadPackageManagerResource;
+pub use robust_downloader::Integrity;
+pub use tarball::ArchiveExtension;
 
 pub async fn download_resource<R>(resource: R) -> anyhow::Result<PathBuf>
+where
+  R: DownloadResource,
+{
+  if resource.is_offline() {
+    return decompress_cached_resource(resource);
+  }
+
+  let mut candidates = resource.get_candidates().await;
+
+  if candidates.len() <= 1 {
+    return download_candidate(candidates.pop().unwrap_or(resource)).await;
+  }
+
+  let mut errors = vec![];
+
+  for candidate in candidates {
+    let url = candidate.get_download_url();
+    // 连接失败、404、校验和不一致都换下一个下载源
+    match download_candidate(candidate).await {
+      Ok(dir) => return Ok(dir),
+      Err(e) => {
+        trace!("Failed to download from {}: {:?}", url, e);
+        errors.push(format!("  {} : {}", url, e));
+      }
+    }
+  }
+
+  bail!(
+    "Failed to download {} from every mirror:\n{}",
+    resource.get_artifact_name(),
+    errors.join("\n")
+  );
+}
+
+async fn download_candidate<R>(resource: R) -> anyhow::Result<PathBuf>
 where
   R: DownloadResource,
 {
@@ -23,9 +70,109 @@ where
 
   downloader.download(vec![download_item.clone()]).await?;
 
+  decompress_verified(&resource, download_item.target)
+}
+
+/// 下载任意地址上的归档，不经过 index.json 和 SHASUMS256.txt，`sha256` 为空时不做校验
+pub async fn download_archive(
+  url: &str,
+  target: PathBuf,
+  sha256: Option<String>,
+) -> anyhow::Result<PathBuf> {
+  let download_item = match sha256 {
+    Some(sha256) => DownloadItem::builder()
+      .url(url.to_string())
+      .target(target.clone())
+      .integrity(Integrity::SHA256(sha256.trim().to_string()))
+      .build(),
+    None => DownloadItem::builder()
+      .url(url.to_string())
+      .target(target.clone())
+      .build(),
+  };
+
+  let downloader = RobustDownloader::builder().max_concurrent(2).build();
+
+  downloader.download(vec![download_item]).await?;
+
+  Ok(target)
+}
+
+/// 离线模式下只能使用 download_dir 中已经下载过的归档
+fn decompress_cached_resource<R>(resource: R) -> anyhow::Result<PathBuf>
+where
+  R: DownloadResource,
+{
+  let archive = resource.get_extract_path();
+
+  if !archive.try_exists()? {
+    bail!(
+      "Offline mode is enabled ( SNM_OFFLINE ), {} is neither installed nor cached at {:?}, it would be downloaded from {}",
+      resource.get_artifact_name(),
+      archive,
+      resource.get_download_url()
+    );
+  }
+
+  trace!("Offline mode, decompress cached archive: {:?}", archive);
+
+  decompress_verified(&resource, archive)
+}
+
+/// 解压归档，并按 `get_pinned_digest_entry` 在解压前校验归档或解压后校验其中的文件
+///
+/// 解压后的文件不一致时同时删除解压目录，避免被当作已安装的版本
+fn decompress_verified<R>(resource: &R, archive: PathBuf) -> anyhow::Result<PathBuf>
+where
+  R: DownloadResource,
+{
   let decompress_dir = resource.get_decompress_dir();
 
-  ArchiveExtension::from_path(download_item.target)?.decompress(&decompress_dir)?;
+  let pinned = resource.get_pinned_digest();
+
+  let entry = resource.get_pinned_digest_entry();
+
+  if let (Some(pinned), None) = (&pinned, &entry) {
+    verify_pinned_digest(resource, &archive, &archive, pinned)?;
+  }
+
+  ArchiveExtension::from_path(archive.clone())?.decompress(&decompress_dir)?;
+
+  if let (Some(pinned), Some(entry)) = (&pinned, &entry) {
+    if let Err(e) = verify_pinned_digest(resource, &decompress_dir.join(entry), &archive, pinned) {
+      fs::remove_dir_all(&decompress_dir)?;
+      return Err(e);
+    }
+  }
 
   Ok(decompress_dir)
 }
+
+/// 校验 `file` 是否满足 `get_pinned_digest` 固定的摘要，不一致时删除归档，避免之后被当作缓存使用
+fn verify_pinned_digest<R>(
+  resource: &R,
+  file: &Path,
+  archive: &Path,
+  pinned: &PinnedDigest,
+) -> anyhow::Result<()>
+where
+  R: DownloadResource,
+{
+  let actual = pinned.digest_file(file)?;
+
+  if actual.eq_ignore_ascii_case(pinned.value()) {
+    return Ok(());
+  }
+
+  fs::remove_file(archive)?;
+
+  bail!(
+    r#"Checksum mismatch for {} ( {:?} ), the archive has been removed
+  expected ( pinned ) : {}
+  actual              : {}"#,
+    resource.get_artifact_name(),
+    file,
+    pinned.value(),
+    actual
+  );
+}
@@ -0,0 +1,336 @@
+use std::{
+  fs,
+  path::PathBuf,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::bail;
+use reqwest::{
+  StatusCode,
+  header::{ACCEPT, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use snm_config::snm_config::SnmConfig;
+use snm_utils::write::write_atomic;
+use tracing::trace;
+
+/// 缓存条目的元信息，和响应体分开存放
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheMeta {
+  url: String,
+  etag: Option<String>,
+  last_modified: Option<String>,
+  fetched_at: u64,
+}
+
+/// index.json、schedule.json 这类远程元数据的磁盘缓存
+///
+/// 缓存未过期（`SnmConfig::metadata_cache_ttl_secs`）时直接读取本地文件；
+/// 过期后带上 `If-None-Match` / `If-Modified-Since` 重新校验，服务端返回 304 时沿用本地文件。
+/// `refresh` 为 true 时跳过缓存，总是重新下载。
+pub struct MetadataCache<'a> {
+  config: &'a SnmConfig,
+  refresh: bool,
+  accept: Option<&'static str>,
+}
+
+impl<'a> MetadataCache<'a> {
+  pub fn new(config: &'a SnmConfig, refresh: bool) -> Self {
+    Self {
+      config,
+      refresh,
+      accept: None,
+    }
+  }
+
+  /// 请求时带上 `Accept`，例如 npm registry 的精简 packument `application/vnd.npm.install-v1+json`
+  pub fn with_accept(self, accept: &'static str) -> Self {
+    Self {
+      accept: Some(accept),
+      ..self
+    }
+  }
+
+  fn dir(&self) -> PathBuf {
+    self.config.cache_dir.join("metadata")
+  }
+
+  fn body_path(&self, name: &str) -> PathBuf {
+    self.dir().join(name)
+  }
+
+  fn meta_path(&self, name: &str) -> PathBuf {
+    self.dir().join(format!("{}.meta.json", name))
+  }
+
+  fn now() -> u64 {
+    SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.as_secs())
+      .unwrap_or_default()
+  }
+
+  fn read_meta(&self, name: &str, url: &str) -> Option<CacheMeta> {
+    let body = self.body_path(name);
+    if !body.is_file() {
+      return None;
+    }
+    let content = fs::read_to_string(self.meta_path(name)).ok()?;
+    serde_json::from_str::<CacheMeta>(&content)
+      .ok()
+      .filter(|meta| meta.url == url)
+  }
+
+  fn write(&self, name: &str, meta: &CacheMeta, body: Option<&[u8]>) -> anyhow::Result<()> {
+    fs::create_dir_all(self.dir())?;
+    if let Some(body) = body {
+      write_atomic(&self.body_path(name), body)?;
+    }
+    write_atomic(&self.meta_path(name), serde_json::to_vec_pretty(meta)?)?;
+    Ok(())
+  }
+
+  fn read_body<T: DeserializeOwned>(&self, name: &str) -> anyhow::Result<T> {
+    let body = fs::read(self.body_path(name))?;
+    Ok(serde_json::from_slice::<T>(&body)?)
+  }
+
+  /// 只读取未过期的缓存，不发请求，`refresh` 或离线时也不读取
+  pub fn get_fresh_json<T: DeserializeOwned>(&self, name: &str, url: &str) -> Option<T> {
+    if self.refresh || self.config.offline {
+      return None;
+    }
+
+    let meta = self.read_meta(name, url)?;
+    let age = Self::now().saturating_sub(meta.fetched_at);
+    if age >= self.config.metadata_cache_ttl_secs {
+      return None;
+    }
+
+    trace!("Metadata cache hit: {} ( age {}s )", url, age);
+    self.read_body::<T>(name).ok()
+  }
+
+  /// 读取 `url` 对应的 JSON，`name` 是缓存文件名
+  pub async fn get_json<T: DeserializeOwned>(&self, name: &str, url: &str) -> anyhow::Result<T> {
+    let cached = if self.refresh {
+      None
+    } else {
+      self.read_meta(name, url)
+    };
+
+    if self.config.offline {
+      if self.body_path(name).is_file() {
+        trace!("Offline mode, use cached metadata: {}", url);
+        return self.read_body::<T>(name);
+      }
+      bail!(
+        "Offline mode is enabled ( SNM_OFFLINE ), {} is not cached, it would be fetched from {}",
+        name,
+        url
+      );
+    }
+
+    if let Some(value) = self.get_fresh_json::<T>(name, url) {
+      return Ok(value);
+    }
+
+    let client = reqwest::Client::builder()
+      .timeout(Duration::from_secs(self.config.download_timeout_secs))
+      .build()?;
+
+    let mut request = client.get(url);
+
+    if let Some(accept) = self.accept {
+      request = request.header(ACCEPT, accept);
+    }
+
+    if let Some(meta) = &cached {
+      if let Some(etag) = &meta.etag {
+        request = request.header(IF_NONE_MATCH, etag);
+      }
+      if let Some(last_modified) = &meta.last_modified {
+        request = request.header(IF_MODIFIED_SINCE, last_modified);
+      }
+    }
+
+    let resp = request.send().await?;
+
+    if resp.status() == StatusCode::NOT_MODIFIED {
+      if let Some(meta) = cached {
+        trace!("Metadata cache revalidated: {}", url);
+        self.write(
+          name,
+          &CacheMeta {
+            fetched_at: Self::now(),
+            ..meta
+          },
+          None,
+        )?;
+        return self.read_body::<T>(name);
+      }
+    }
+
+    let resp = resp.error_for_status()?;
+
+    let header = |key| {
+      resp
+        .headers()
+        .get(key)
+        .and_then(|v: &reqwest::header::HeaderValue| v.to_str().ok())
+        .map(|v| v.to_string())
+    };
+
+    let meta = CacheMeta {
+      url: url.to_string(),
+      etag: header(ETAG),
+      last_modified: header(LAST_MODIFIED),
+      fetched_at: Self::now(),
+    };
+
+    let body = resp.bytes().await?;
+
+    let value = serde_json::from_slice::<T>(&body)?;
+
+    trace!("Metadata cache updated: {}", url);
+
+    self.write(name, &meta, Some(&body))?;
+
+    Ok(value)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use snm_test_utils::SnmTestContext;
+  use test_context::test_context;
+  use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{header, method, path},
+  };
+
+  use super::*;
+
+  fn config(ctx: &mut SnmTestContext, ttl: u64) -> anyhow::Result<SnmConfig> {
+    ctx.set_envs(&[
+      (
+        format!("{}_HOME_DIR", ctx.get_id()),
+        ctx.get_temp_dir().to_string_lossy().to_string(),
+      ),
+      (
+        format!("{}_METADATA_CACHE_TTL_SECS", ctx.get_id()),
+        ttl.to_string(),
+      ),
+    ]);
+    SnmConfig::from(ctx.get_id(), ctx.get_temp_dir())
+  }
+
+  #[test_context(SnmTestContext)]
+  #[tokio::test]
+  async fn should_serve_from_cache_within_ttl(ctx: &mut SnmTestContext) -> anyhow::Result<()> {
+    let config = config(ctx, 3600)?;
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+      .and(path("/index.json"))
+      .respond_with(ResponseTemplate::new(200).set_body_string(r#"["v1"]"#))
+      .expect(2)
+      .mount(&server)
+      .await;
+
+    let url = format!("{}/index.json", server.uri());
+
+    let first: Vec<String> = MetadataCache::new(&config, false)
+      .get_json("index.json", &url)
+      .await?;
+    let second: Vec<String> = MetadataCache::new(&config, false)
+      .get_json("index.json", &url)
+      .await?;
+    let refreshed: Vec<String> = MetadataCache::new(&config, true)
+      .get_json("index.json", &url)
+      .await?;
+
+    assert_eq!(first, vec!["v1"]);
+    assert_eq!(second, vec!["v1"]);
+    assert_eq!(refreshed, vec!["v1"]);
+
+    Ok(())
+  }
+
+  #[test_context(SnmTestContext)]
+  #[tokio::test]
+  async fn should_revalidate_with_etag(ctx: &mut SnmTestContext) -> anyhow::Result<()> {
+    let config = config(ctx, 0)?;
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+      .and(path("/index.json"))
+      .and(header("if-none-match", "\"abc\""))
+      .respond_with(ResponseTemplate::new(304))
+      .expect(1)
+      .mount(&server)
+      .await;
+
+    Mock::given(method("GET"))
+      .and(path("/index.json"))
+      .respond_with(
+        ResponseTemplate::new(200)
+          .insert_header("etag", "\"abc\"")
+          .set_body_string(r#"["v1"]"#),
+      )
+      .expect(1)
+      .mount(&server)
+      .await;
+
+    let url = format!("{}/index.json", server.uri());
+
+    let first: Vec<String> = MetadataCache::new(&config, false)
+      .get_json("index.json", &url)
+      .await?;
+    let second: Vec<String> = MetadataCache::new(&config, false)
+      .get_json("index.json", &url)
+      .await?;
+
+    assert_eq!(first, second);
+
+    Ok(())
+  }
+
+  #[test_context(SnmTestContext)]
+  #[tokio::test]
+  async fn should_only_read_cache_when_offline(ctx: &mut SnmTestContext) -> anyhow::Result<()> {
+    let mut config = config(ctx, 0)?;
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+      .and(path("/index.json"))
+      .respond_with(ResponseTemplate::new(200).set_body_string(r#"["v1"]"#))
+      .expect(1)
+      .mount(&server)
+      .await;
+
+    let url = format!("{}/index.json", server.uri());
+
+    config.offline = true;
+
+    let missing = MetadataCache::new(&config, false)
+      .get_json::<Vec<String>>("index.json", &url)
+      .await;
+    assert!(missing.unwrap_err().to_string().contains("index.json"));
+
+    config.offline = false;
+
+    MetadataCache::new(&config, false)
+      .get_json::<Vec<String>>("index.json", &url)
+      .await?;
+
+    config.offline = true;
+
+    let cached: Vec<String> = MetadataCache::new(&config, true)
+      .get_json("index.json", &url)
+      .await?;
+    assert_eq!(cached, vec!["v1"]);
+
+    Ok(())
+  }
+}
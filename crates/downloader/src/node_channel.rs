@@ -0,0 +1,85 @@
+use std::fmt::Display;
+
+/// Node.js 的发布渠道，不同渠道的产物放在不同的目录下
+///
+/// - release：`https://nodejs.org/dist/v20.11.0/`
+/// - rc：`https://nodejs.org/download/rc/v23.0.0-rc.2/`
+/// - nightly：`https://nodejs.org/download/nightly/v24.0.0-nightly20250101ab12cd34ef/`
+/// - test：`https://nodejs.org/download/test/v21.0.0-test20230901ab12cd34ef/`
+/// - v8-canary：`https://nodejs.org/download/v8-canary/v22.0.0-v8-canary20231127ab12cd34ef/`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeChannel {
+  Release,
+  Rc,
+  Nightly,
+  Test,
+  V8Canary,
+}
+
+impl NodeChannel {
+  /// 根据版本号的预发布标识判断渠道，未知的预发布标识返回 `None`
+  pub fn from_version(version: &str) -> Option<Self> {
+    let version = version.trim().trim_start_matches('v');
+
+    let Some((_, pre)) = version.split_once('-') else {
+      return Some(NodeChannel::Release);
+    };
+
+    [
+      ("rc.", NodeChannel::Rc),
+      ("nightly", NodeChannel::Nightly),
+      ("test", NodeChannel::Test),
+      ("v8-canary", NodeChannel::V8Canary),
+    ]
+    .into_iter()
+    .find(|(prefix, _)| pre.starts_with(prefix))
+    .map(|(_, channel)| channel)
+  }
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      NodeChannel::Release => "release",
+      NodeChannel::Rc => "rc",
+      NodeChannel::Nightly => "nightly",
+      NodeChannel::Test => "test",
+      NodeChannel::V8Canary => "v8-canary",
+    }
+  }
+
+  /// 由 `node_dist_url` 推导出渠道对应的地址
+  ///
+  /// release 直接使用 `node_dist_url`；其他渠道把结尾的 `/dist` 换成 `/download/<channel>`，
+  /// 镜像的目录结构和 nodejs.org 一致时同样适用
+  pub fn dist_url(&self, node_dist_url: &str) -> String {
+    let node_dist_url = node_dist_url.trim_end_matches('/');
+
+    if *self == NodeChannel::Release {
+      return node_dist_url.to_string();
+    }
+
+    let base = node_dist_url.strip_suffix("/dist").unwrap_or(node_dist_url);
+
+    format!("{}/download/{}", base, self.as_str())
+  }
+
+  /// 由 unofficial-builds 的地址推导出渠道对应的地址，`.../download/release` 换成 `.../download/<channel>`
+  pub fn unofficial_url(&self, node_unofficial_dist_url: &str) -> String {
+    let node_unofficial_dist_url = node_unofficial_dist_url.trim_end_matches('/');
+
+    if *self == NodeChannel::Release {
+      return node_unofficial_dist_url.to_string();
+    }
+
+    let base = node_unofficial_dist_url
+      .strip_suffix("/release")
+      .unwrap_or(node_unofficial_dist_url);
+
+    format!("{}/{}", base, self.as_str())
+  }
+}
+
+impl Display for NodeChannel {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
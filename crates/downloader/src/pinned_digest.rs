@@ -0,0 +1,55 @@
+use std::{fmt::Write, fs::File, io, path::Path};
+
+use sha1::Sha1;
+use sha2::{Digest, Sha224, Sha256, Sha512};
+
+/// 下载完成后额外校验的十六进制摘要，例如 packageManager 中 corepack 风格的 `+sha224.<hex>`
+///
+/// robust_downloader 的 `Integrity` 不支持 sha224，而 `yarn set version` 默认写入的正是 sha224，所以单独计算
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PinnedDigest {
+  Sha1(String),
+  Sha224(String),
+  Sha256(String),
+  Sha512(String),
+}
+
+impl PinnedDigest {
+  pub fn value(&self) -> &str {
+    match self {
+      PinnedDigest::Sha1(value)
+      | PinnedDigest::Sha224(value)
+      | PinnedDigest::Sha256(value)
+      | PinnedDigest::Sha512(value) => value,
+    }
+  }
+
+  /// 按同一算法计算文件的十六进制摘要
+  pub fn digest_file(&self, path: &Path) -> anyhow::Result<String> {
+    match self {
+      PinnedDigest::Sha1(_) => digest_file::<Sha1>(path),
+      PinnedDigest::Sha224(_) => digest_file::<Sha224>(path),
+      PinnedDigest::Sha256(_) => digest_file::<Sha256>(path),
+      PinnedDigest::Sha512(_) => digest_file::<Sha512>(path),
+    }
+  }
+
+  /// 文件摘要是否与固定的摘要一致，十六进制不区分大小写
+  pub fn verify(&self, path: &Path) -> anyhow::Result<bool> {
+    Ok(self.digest_file(path)?.eq_ignore_ascii_case(self.value()))
+  }
+}
+
+fn digest_file<D: Digest + io::Write>(path: &Path) -> anyhow::Result<String> {
+  let mut hasher = D::new();
+  io::copy(&mut File::open(path)?, &mut hasher)?;
+  Ok(
+    hasher
+      .finalize()
+      .iter()
+      .fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+      }),
+  )
+}
@@ -2,6 +2,8 @@ use std::{path::PathBuf, pin::Pin, time::Duration};
 
 use robust_downloader::{DownloadItem, Integrity};
 
+use crate::PinnedDigest;
+
 pub trait DownloadResource {
   /// 获取下载项配置
   fn get_download_url(&self) -> String;
@@ -18,6 +20,26 @@ pub trait DownloadResource {
 
   fn get_decompress_dir(&self) -> PathBuf;
 
+  /// 离线模式下不允许发起网络请求
+  fn is_offline(&self) -> bool;
+
+  /// 候选的下载源，`download_resource` 按顺序依次尝试，直到有一个成功
+  fn get_candidates<'async_trait>(
+    &self,
+  ) -> Pin<Box<dyn Future<Output = Vec<Self>> + Send + 'async_trait>>
+  where
+    Self: Sized + 'async_trait;
+
+  /// 下载源校验和之外还必须满足的摘要，例如 packageManager 中的 `+sha512.<digest>`
+  fn get_pinned_digest(&self) -> Option<PinnedDigest> {
+    None
+  }
+
+  /// `get_pinned_digest` 校验的解压后文件，相对于 `get_decompress_dir`，`None` 时校验下载的归档
+  fn get_pinned_digest_entry(&self) -> Option<PathBuf> {
+    None
+  }
+
   fn get_expect_shasum<'async_trait>(
     &self,
   ) -> Pin<Box<dyn Future<Output = anyhow::Result<Integrity>> + Send + 'async_trait>>
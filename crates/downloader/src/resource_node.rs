@@ -1,20 +1,109 @@
-use std::{collections::HashMap, path::PathBuf, pin::Pin, time::Duration};
+use std::{
+  collections::HashMap,
+  path::PathBuf,
+  pin::Pin,
+  time::{Duration, Instant},
+};
 
-use anyhow::Context;
+use anyhow::{Context, bail};
+use futures_util::future::join_all;
 use robust_downloader::{DownloadItem, Integrity};
-use snm_config::snm_config::SnmConfig;
+use snm_config::{mirror_strategy::MirrorStrategy, platform::Platform, snm_config::SnmConfig};
+use tracing::trace;
 use typed_builder::TypedBuilder;
 
-use crate::DownloadResource;
+use crate::{DownloadResource, NodeChannel};
 
 #[derive(Debug, Clone, TypedBuilder)]
 pub struct DownloadNodeResource<'a> {
   pub config: &'a SnmConfig,
   pub bin_name: String,
   pub version: String,
+  /// 使用的镜像，未指定时使用 `SnmConfig::node_dist_urls` 中的第一个
+  #[builder(default, setter(strip_option))]
+  pub mirror: Option<String>,
 }
 
 impl<'a> DownloadNodeResource<'a> {
+  fn channel(&self) -> NodeChannel {
+    NodeChannel::from_version(&self.version).unwrap_or(NodeChannel::Release)
+  }
+
+  /// 下载这个版本使用的平台，旧版 glibc 上可能换成 glibc-217 构建
+  fn platform(&self) -> Platform {
+    self.config.platform.for_version(&self.version)
+  }
+
+  fn mirror(&self) -> &str {
+    self
+      .mirror
+      .as_deref()
+      .or(self.config.node_dist_urls.first().map(|url| url.as_str()))
+      .unwrap_or("https://nodejs.org/dist")
+  }
+
+  /// 版本所属渠道的下载地址，预发布版本不在 `/dist` 下，musl 等产物只在 unofficial-builds 中
+  fn dist_url(&self) -> String {
+    if self.platform().is_unofficial() {
+      return self
+        .channel()
+        .unofficial_url(&self.config.node_unofficial_dist_url);
+    }
+    self.channel().dist_url(self.mirror())
+  }
+
+  /// SHASUMS256.txt 的地址，配置了可信来源时总是从可信来源获取，否则和归档来自同一个镜像
+  ///
+  /// 官方的 SHASUMS256.txt 中没有 unofficial-builds 的产物，这种情况下总是和归档来自同一处
+  fn shasums_url(&self) -> String {
+    let host = match &self.config.node_shasums_url {
+      Some(trusted) if !self.platform().is_unofficial() => self.channel().dist_url(trusted),
+      _ => self.dist_url(),
+    };
+
+    format!(
+      "{host}/v{version}/SHASUMS256.txt",
+      host = host,
+      version = self.version.trim().trim_start_matches('v')
+    )
+  }
+
+  /// 并发请求各镜像的 SHASUMS256.txt，按响应时间从快到慢排序，请求失败的镜像排在最后
+  async fn rank_mirrors(mirrors: Vec<(String, String)>, timeout: Duration) -> Vec<String> {
+    let Ok(client) = reqwest::Client::builder().timeout(timeout).build() else {
+      return mirrors.into_iter().map(|(mirror, _)| mirror).collect();
+    };
+
+    let probes = mirrors
+      .into_iter()
+      .enumerate()
+      .map(|(index, (mirror, url))| {
+        let client = client.clone();
+        async move {
+          let start = Instant::now();
+          let ok = client
+            .head(&url)
+            .send()
+            .await
+            .is_ok_and(|resp| resp.status().is_success());
+          let elapsed = start.elapsed();
+          trace!("Probe mirror {} ( ok: {}, {:?} )", url, ok, elapsed);
+          (
+            !ok,
+            if ok { elapsed } else { Duration::ZERO },
+            index,
+            mirror,
+          )
+        }
+      });
+
+    let mut ranked = join_all(probes).await;
+
+    ranked.sort();
+
+    ranked.into_iter().map(|(_, _, _, mirror)| mirror).collect()
+  }
+
   fn parse_shasum(content: &str) -> HashMap<String, String> {
     content
       .lines()
@@ -47,7 +136,7 @@ impl<'a> DownloadResource for DownloadNodeResource<'a> {
   fn get_download_url(&self) -> String {
     format!(
       "{host}/v{version}/{artifact_name}",
-      host = self.config.node_dist_url,
+      host = self.dist_url(),
       version = &self.version,
       artifact_name = self.get_artifact_name()
     )
@@ -55,11 +144,10 @@ impl<'a> DownloadResource for DownloadNodeResource<'a> {
 
   fn get_artifact_name(&self) -> String {
     format!(
-      "{bin_name}-v{version}-{os}-{arch}.{ext}",
+      "{bin_name}-v{version}-{target}.{ext}",
       bin_name = self.bin_name,
       version = &self.version,
-      os = self.config.platform.os,
-      arch = self.config.platform.arch,
+      target = self.platform().target(),
       ext = self.config.platform.ext
     )
   }
@@ -79,7 +167,55 @@ impl<'a> DownloadResource for DownloadNodeResource<'a> {
   }
 
   fn get_decompress_dir(&self) -> PathBuf {
-    self.config.node_bin_dir.join(&self.version)
+    self.config.node_home_dir(&self.version)
+  }
+
+  fn is_offline(&self) -> bool {
+    self.config.offline
+  }
+
+  fn get_candidates<'async_trait>(
+    &self,
+  ) -> Pin<Box<dyn Future<Output = Vec<Self>> + Send + 'async_trait>>
+  where
+    Self: Sized + 'async_trait,
+  {
+    let resource = self.clone();
+
+    Box::pin(async move {
+      // 显式指定了镜像或者只能使用 unofficial-builds 时只有一个下载源
+      if resource.mirror.is_some() || resource.platform().is_unofficial() {
+        return vec![resource];
+      }
+
+      let mirrors = resource.config.node_dist_urls.clone();
+
+      let mirrors = match resource.config.node_dist_strategy {
+        MirrorStrategy::Fastest if mirrors.len() > 1 => {
+          let probes = mirrors
+            .into_iter()
+            .map(|mirror| {
+              let url = DownloadNodeResource {
+                mirror: Some(mirror.clone()),
+                ..resource.clone()
+              }
+              .shasums_url();
+              (mirror, url)
+            })
+            .collect();
+          Self::rank_mirrors(probes, resource.get_timeout_secs()).await
+        }
+        _ => mirrors,
+      };
+
+      mirrors
+        .into_iter()
+        .map(|mirror| DownloadNodeResource {
+          mirror: Some(mirror),
+          ..resource.clone()
+        })
+        .collect()
+    })
   }
 
   fn get_expect_shasum<'async_trait>(
@@ -88,27 +224,28 @@ impl<'a> DownloadResource for DownloadNodeResource<'a> {
   where
     Self: 'async_trait,
   {
-    let version = self.version.clone();
-    let node_dist_url = self.config.node_dist_url.clone();
+    let sha256_url = self.shasums_url();
     let timeout = self.get_timeout_secs();
     let file_name = self.get_artifact_name();
+    let offline = self.is_offline();
 
     Box::pin(async move {
-      let version = version
-        .trim()
-        .to_lowercase()
-        .trim_start_matches("v")
-        .to_owned();
-
-      let sha256_url = format!(
-        "{host}/v{version}/SHASUMS256.txt",
-        host = node_dist_url,
-        version = &version
-      );
+      if offline {
+        bail!(
+          "Offline mode is enabled ( SNM_OFFLINE ), can not fetch SHASUMS256.txt for {}",
+          file_name
+        );
+      }
 
       let client = reqwest::Client::builder().timeout(timeout).build()?;
 
-      let sha256_str = client.get(sha256_url).send().await?.text().await?;
+      let sha256_str = client
+        .get(&sha256_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
 
       let shasums = Self::parse_shasum(&sha256_str);
 
@@ -121,3 +258,107 @@ impl<'a> DownloadResource for DownloadNodeResource<'a> {
     })
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use snm_test_utils::SnmTestContext;
+  use test_context::test_context;
+  use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path},
+  };
+
+  use super::*;
+
+  async fn mirror(delay: u64, status: u16) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("HEAD"))
+      .and(path("/v20.11.0/SHASUMS256.txt"))
+      .respond_with(ResponseTemplate::new(status).set_delay(Duration::from_millis(delay)))
+      .mount(&server)
+      .await;
+    server
+  }
+
+  #[test_context(SnmTestContext)]
+  #[tokio::test]
+  async fn should_rank_fastest_mirror_first(ctx: &mut SnmTestContext) -> anyhow::Result<()> {
+    let slow = mirror(300, 200).await;
+    let fast = mirror(0, 200).await;
+    let broken = mirror(0, 404).await;
+
+    let urls = [broken.uri(), slow.uri(), fast.uri()];
+
+    ctx.set_envs(&[
+      (
+        format!("{}_HOME_DIR", ctx.get_id()),
+        ctx.get_temp_dir().to_string_lossy().to_string(),
+      ),
+      (format!("{}_NODE_DIST_URL", ctx.get_id()), urls.join(",")),
+      (
+        format!("{}_NODE_DIST_STRATEGY", ctx.get_id()),
+        String::from("fastest"),
+      ),
+    ]);
+
+    let config = SnmConfig::from(ctx.get_id(), ctx.get_temp_dir())?;
+
+    let resource = DownloadNodeResource::builder()
+      .config(&config)
+      .bin_name(String::from("node"))
+      .version(String::from("20.11.0"))
+      .build();
+
+    let candidates = resource
+      .get_candidates()
+      .await
+      .into_iter()
+      .filter_map(|candidate| candidate.mirror)
+      .collect::<Vec<_>>();
+
+    assert_eq!(candidates, vec![fast.uri(), slow.uri(), broken.uri()]);
+
+    Ok(())
+  }
+
+  #[test_context(SnmTestContext)]
+  #[tokio::test]
+  async fn should_fetch_shasums_from_trusted_source(
+    ctx: &mut SnmTestContext,
+  ) -> anyhow::Result<()> {
+    ctx.set_envs(&[
+      (
+        format!("{}_HOME_DIR", ctx.get_id()),
+        ctx.get_temp_dir().to_string_lossy().to_string(),
+      ),
+      (
+        format!("{}_NODE_DIST_URL", ctx.get_id()),
+        String::from("https://mirror.example.com/node"),
+      ),
+      (
+        format!("{}_NODE_SHASUMS_URL", ctx.get_id()),
+        String::from("https://nodejs.org/dist"),
+      ),
+    ]);
+
+    let config = SnmConfig::from(ctx.get_id(), ctx.get_temp_dir())?;
+
+    let resource = DownloadNodeResource::builder()
+      .config(&config)
+      .bin_name(String::from("node"))
+      .version(String::from("23.0.0-rc.2"))
+      .build();
+
+    assert!(
+      resource
+        .get_download_url()
+        .starts_with("https://mirror.example.com/node/download/rc/v23.0.0-rc.2/")
+    );
+    assert_eq!(
+      resource.shasums_url(),
+      "https://nodejs.org/download/rc/v23.0.0-rc.2/SHASUMS256.txt"
+    );
+
+    Ok(())
+  }
+}
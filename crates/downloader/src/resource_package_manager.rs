@@ -1,11 +1,13 @@
-use std::{path::PathBuf, pin::Pin, time::Duration};
+use std::{fmt::Write, path::PathBuf, pin::Pin, time::Duration};
 
+use anyhow::{Context, bail};
+use base64::{Engine, engine::general_purpose::STANDARD};
 use robust_downloader::{DownloadItem, Integrity};
 use snm_config::snm_config::SnmConfig;
 use snm_utils::ver::ver_gt_1;
 use typed_builder::TypedBuilder;
 
-use crate::resource::DownloadResource;
+use crate::{PinnedDigest, resource::DownloadResource};
 
 #[derive(serde::Deserialize)]
 struct NpmResponse {
@@ -14,7 +16,59 @@ struct NpmResponse {
 
 #[derive(serde::Deserialize)]
 struct Dist {
-  shasum: String,
+  /// SRI，例如 `sha512-<base64>`，较老的包没有
+  integrity: Option<String>,
+  shasum: Option<String>,
+}
+
+impl Dist {
+  /// 优先使用 SRI 中最强的摘要，只有没有 `integrity` 时才退回 sha1 的 `shasum`
+  fn integrity(&self) -> anyhow::Result<Integrity> {
+    if let Some(sri) = &self.integrity {
+      return parse_sri(sri);
+    }
+
+    match &self.shasum {
+      Some(shasum) => Ok(Integrity::SHA1(shasum.clone())),
+      None => bail!("The registry returned neither dist.integrity nor dist.shasum"),
+    }
+  }
+}
+
+/// 解析 SRI，有多个摘要时选择最强的，摘要从 base64 转为和下载校验一致的十六进制
+fn parse_sri(sri: &str) -> anyhow::Result<Integrity> {
+  let (algorithm, digest) = sri
+    .split_whitespace()
+    .filter_map(|item| item.split_once('-'))
+    .filter_map(|(algorithm, digest)| {
+      let rank = match algorithm {
+        "sha512" => 3,
+        "sha256" => 2,
+        "sha1" => 1,
+        _ => return None,
+      };
+      // 去掉 `?` 之后的选项
+      let digest = digest.split('?').next().unwrap_or(digest);
+      Some((rank, algorithm, digest))
+    })
+    .max_by_key(|(rank, ..)| *rank)
+    .map(|(_, algorithm, digest)| (algorithm, digest))
+    .with_context(|| format!("Unsupported dist.integrity: {}", sri))?;
+
+  let hex = STANDARD
+    .decode(digest)
+    .with_context(|| format!("Invalid base64 digest in dist.integrity: {}", sri))?
+    .iter()
+    .fold(String::new(), |mut hex, byte| {
+      let _ = write!(hex, "{:02x}", byte);
+      hex
+    });
+
+  Ok(match algorithm {
+    "sha512" => Integrity::SHA512(hex),
+    "sha256" => Integrity::SHA256(hex),
+    _ => Integrity::SHA1(hex),
+  })
 }
 
 #[derive(Debug, Clone, TypedBuilder)]
@@ -22,6 +76,9 @@ pub struct DownloadPackageManagerResource<'a> {
   pub config: &'a SnmConfig,
   pub bin_name: String,
   pub version: String,
+  /// packageManager 中 `+<algorithm>.<digest>` 固定的摘要
+  #[builder(default)]
+  pub pinned_digest: Option<PinnedDigest>,
 }
 
 impl<'a> DownloadResource for DownloadPackageManagerResource<'a> {
@@ -76,6 +133,35 @@ impl<'a> DownloadResource for DownloadPackageManagerResource<'a> {
       .join(&self.version)
   }
 
+  fn is_offline(&self) -> bool {
+    self.config.offline
+  }
+
+  fn get_pinned_digest(&self) -> Option<PinnedDigest> {
+    self.pinned_digest.clone()
+  }
+
+  /// 和 corepack 一样，yarn 2+ 固定的是 `bin/yarn.js` 的摘要而不是 @yarnpkg/cli-dist 的 tgz
+  fn get_pinned_digest_entry(&self) -> Option<PathBuf> {
+    match (
+      self.bin_name.as_str(),
+      ver_gt_1(&self.version).unwrap_or(false),
+    ) {
+      ("yarn" | "yarnpkg", true) => Some(PathBuf::from("bin/yarn.js")),
+      _ => None,
+    }
+  }
+
+  fn get_candidates<'async_trait>(
+    &self,
+  ) -> Pin<Box<dyn Future<Output = Vec<Self>> + Send + 'async_trait>>
+  where
+    Self: Sized + 'async_trait,
+  {
+    let candidates = vec![self.clone()];
+    Box::pin(async move { candidates })
+  }
+
   fn get_expect_shasum<'async_trait>(
     &self,
   ) -> Pin<Box<dyn Future<Output = anyhow::Result<Integrity>> + Send + 'async_trait>>
@@ -86,8 +172,17 @@ impl<'a> DownloadResource for DownloadPackageManagerResource<'a> {
     let version = self.version.clone();
     let npm_registry = self.config.npm_registry.clone();
     let timeout = self.get_timeout_secs();
+    let offline = self.is_offline();
+    let artifact_name = self.get_artifact_name();
 
     Box::pin(async move {
+      if offline {
+        bail!(
+          "Offline mode is enabled ( SNM_OFFLINE ), can not fetch the registry checksum for {}",
+          artifact_name
+        );
+      }
+
       let namespace = match (bin_name.as_str(), ver_gt_1(&version).unwrap_or(false)) {
         ("yarn" | "yarnpkg", true) => "@yarnpkg/cli-dist",
         (name, _) => name,
@@ -99,7 +194,188 @@ impl<'a> DownloadResource for DownloadPackageManagerResource<'a> {
 
       let resp = client.get(&url).send().await?.json::<NpmResponse>().await?;
 
-      Ok(Integrity::SHA1(resp.dist.shasum))
+      resp.dist.integrity()
     })
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use sha2::Digest;
+  use snm_test_utils::SnmTestContext;
+  use test_context::test_context;
+  use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path},
+  };
+
+  use super::*;
+
+  #[test]
+  fn should_parse_strongest_sri() -> anyhow::Result<()> {
+    // "hello" 的 sha1 / sha512
+    let sha1 = "qvTGHdzF6KLavt4PO0gs2a6pQ00=";
+    let sha512 =
+      "m3HSJL1i83hdltRq0+o9czGb+8KJDKra4t/3JRlnPKcjI8PZm6XBHXx6zG4UuMXaDEZjR1wuXDre9G9zvN7AQw==";
+
+    let Integrity::SHA512(hex) = parse_sri(&format!("sha1-{} sha512-{}", sha1, sha512))? else {
+      panic!("expected sha512");
+    };
+    assert_eq!(
+      hex,
+      "9b71d224bd62f3785d96d46ad3ea3d73319bfbc2890caadae2dff72519673ca72323c3d99ba5c11d7c7acc6e14b8c5da0c4663475c2e5c3adef46f73bcdec043"
+    );
+
+    let Integrity::SHA1(hex) = parse_sri(&format!("sha1-{}", sha1))? else {
+      panic!("expected sha1");
+    };
+    assert_eq!(hex, "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d");
+
+    assert!(parse_sri("md5-XUFAKrxLKna5cZ2REBfFkg==").is_err());
+
+    Ok(())
+  }
+
+  #[test_context(SnmTestContext)]
+  #[tokio::test]
+  async fn should_prefer_integrity_over_shasum(ctx: &mut SnmTestContext) -> anyhow::Result<()> {
+    let registry = MockServer::start().await;
+
+    Mock::given(method("GET"))
+      .and(path("/pnpm/9.1.0"))
+      .respond_with(ResponseTemplate::new(200).set_body_raw(
+        r#"{"dist":{"shasum":"aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d","integrity":"sha512-m3HSJL1i83hdltRq0+o9czGb+8KJDKra4t/3JRlnPKcjI8PZm6XBHXx6zG4UuMXaDEZjR1wuXDre9G9zvN7AQw=="}}"#,
+        "application/json",
+      ))
+      .mount(&registry)
+      .await;
+
+    Mock::given(method("GET"))
+      .and(path("/pnpm/6.0.0"))
+      .respond_with(ResponseTemplate::new(200).set_body_raw(
+        r#"{"dist":{"shasum":"aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d"}}"#,
+        "application/json",
+      ))
+      .mount(&registry)
+      .await;
+
+    ctx.set_envs(&[
+      (
+        format!("{}_HOME_DIR", ctx.get_id()),
+        ctx.get_temp_dir().to_string_lossy().to_string(),
+      ),
+      (format!("{}_NPM_REGISTRY", ctx.get_id()), registry.uri()),
+    ]);
+
+    let config = SnmConfig::from(ctx.get_id(), ctx.get_temp_dir())?;
+
+    let resource = |version: &str| {
+      DownloadPackageManagerResource::builder()
+        .config(&config)
+        .bin_name(String::from("pnpm"))
+        .version(version.to_string())
+        .build()
+    };
+
+    assert!(matches!(
+      resource("9.1.0").get_expect_shasum().await?,
+      Integrity::SHA512(_)
+    ));
+    assert!(matches!(
+      resource("6.0.0").get_expect_shasum().await?,
+      Integrity::SHA1(_)
+    ));
+
+    Ok(())
+  }
+
+  /// 构造和 @yarnpkg/cli-dist 结构相同的 tgz
+  fn cli_dist_tgz(bundle: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+      Vec::new(),
+      flate2::Compression::default(),
+    ));
+
+    for (path, content) in [
+      (
+        "package/package.json",
+        br#"{"name":"@yarnpkg/cli-dist"}"#.as_slice(),
+      ),
+      ("package/bin/yarn.js", bundle),
+    ] {
+      let mut header = tar::Header::new_gnu();
+      header.set_size(content.len() as u64);
+      header.set_mode(0o644);
+      header.set_cksum();
+      builder.append_data(&mut header, path, content)?;
+    }
+
+    Ok(builder.into_inner()?.finish()?)
+  }
+
+  #[test_context(SnmTestContext)]
+  #[tokio::test]
+  async fn should_verify_yarn_berry_digest_against_bundle(
+    ctx: &mut SnmTestContext,
+  ) -> anyhow::Result<()> {
+    let bundle = b"#!/usr/bin/env node\nconsole.log('4.1.0')\n";
+    let tgz = cli_dist_tgz(bundle)?;
+
+    let registry = MockServer::start().await;
+
+    Mock::given(method("GET"))
+      .and(path("/@yarnpkg/cli-dist/4.1.0"))
+      .respond_with(ResponseTemplate::new(200).set_body_raw(
+        format!(
+          r#"{{"dist":{{"integrity":"sha512-{}"}}}}"#,
+          STANDARD.encode(sha2::Sha512::digest(&tgz))
+        ),
+        "application/json",
+      ))
+      .mount(&registry)
+      .await;
+
+    Mock::given(method("GET"))
+      .and(path("/@yarnpkg/cli-dist/-/cli-dist-4.1.0.tgz"))
+      .respond_with(ResponseTemplate::new(200).set_body_bytes(tgz.clone()))
+      .mount(&registry)
+      .await;
+
+    ctx.set_envs(&[
+      (
+        format!("{}_HOME_DIR", ctx.get_id()),
+        ctx.get_temp_dir().to_string_lossy().to_string(),
+      ),
+      (format!("{}_NPM_REGISTRY", ctx.get_id()), registry.uri()),
+    ]);
+
+    let config = SnmConfig::from(ctx.get_id(), ctx.get_temp_dir())?;
+
+    let hex = |digest: &[u8]| {
+      digest.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+      })
+    };
+
+    let resource = |digest: String| {
+      DownloadPackageManagerResource::builder()
+        .config(&config)
+        .bin_name(String::from("yarn"))
+        .version(String::from("4.1.0"))
+        .pinned_digest(Some(PinnedDigest::Sha224(digest)))
+        .build()
+    };
+
+    // `yarn set version` 写入的是 bin/yarn.js 的 sha224
+    let dir = crate::download_resource(resource(hex(&sha2::Sha224::digest(bundle)))).await?;
+    assert_eq!(std::fs::read(dir.join("bin").join("yarn.js"))?, bundle);
+
+    // tgz 本身的摘要不是 yarn 固定的值
+    let tampered = crate::download_resource(resource(hex(&sha2::Sha224::digest(&tgz)))).await;
+    assert!(tampered.is_err());
+    assert!(!dir.try_exists()?);
+
+    Ok(())
+  }
+}
@@ -1,7 +1,21 @@
+mod lts;
+mod metadata;
 mod node;
+mod node_alias;
+mod node_usage;
 mod node_version;
+mod node_version_file;
 mod node_version_home;
+mod node_version_policy;
+mod node_version_req;
 
+pub use lts::*;
+pub use metadata::*;
 pub use node::*;
+pub use node_alias::*;
+pub use node_usage::*;
 pub use node_version::*;
+pub use node_version_file::*;
 pub use node_version_home::*;
+pub use node_version_policy::*;
+pub use node_version_req::*;
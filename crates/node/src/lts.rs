@@ -6,6 +6,16 @@ pub enum Lts {
   Bool(bool),
 }
 
+impl Lts {
+  /// LTS 版本线的代号，非 LTS 版本返回 `None`
+  pub fn codename(&self) -> Option<&str> {
+    match self {
+      Lts::Str(s) => Some(s),
+      Lts::Bool(_) => None,
+    }
+  }
+}
+
 impl Serialize for Lts {
   fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
   where
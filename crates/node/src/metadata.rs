@@ -0,0 +1,275 @@
+use std::{collections::HashMap, fmt};
+
+use chrono::{NaiveDate, Utc};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use snm_config::snm_config::SnmConfig;
+use snm_downloader::MetadataCache;
+use tracing::trace;
+
+use crate::Lts;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NodeMetadata {
+  pub version: String,
+  pub date: String,
+  // pub files: Vec<String>,
+  pub npm: Option<String>,
+  pub v8: String,
+  pub uv: Option<String>,
+  pub zlib: Option<String>,
+  pub openssl: Option<String>,
+  // pub modules: Option<String>,
+  pub lts: Lts,
+  pub security: bool,
+  // pub end: Option<String>,
+  // pub current: Option<String>,
+  // pub deprecated: Option<bool>,
+  pub schedule: Option<ScheduleMetadata>,
+
+  /// 是否是当前的 default 版本，index.json 中没有这个字段，由 `snm node list` 填充
+  #[serde(default)]
+  pub default: Option<bool>,
+
+  /// 本地是否已安装，由 `snm node list --remote` 填充
+  #[serde(default)]
+  pub installed: Option<bool>,
+}
+
+impl fmt::Display for NodeMetadata {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let v = &self.version[1..];
+
+    let lts = match &self.lts {
+      Lts::Str(s) => format!("lts ( {} )", s),
+      Lts::Bool(b) if *b => "lts".to_string(),
+      _ => "".to_string(),
+    };
+
+    // schedule.json 里没有的大版本不显示停止维护时间
+    let died_on = self
+      .schedule
+      .as_ref()
+      .map(|s| format!("died on {}", s.end.clone()))
+      .unwrap_or(String::new());
+
+    let ssl = self
+      .openssl
+      .as_ref()
+      .map(|s| format!("openssl {}", s.clone()))
+      .unwrap_or(String::new());
+
+    let npm = self
+      .npm
+      .as_ref()
+      .map(|s| format!("npm {}", s.clone()))
+      .unwrap_or(String::new());
+
+    let eol = self.is_eol(Utc::now().date_naive());
+
+    write!(
+      f,
+      r#"{pdd:<2} {v:<12} {installed:<1} {npm:<20} {ssl:<20} {died_on:<22} {nike_name:<10}"#,
+      pdd = self
+        .default
+        .map(|d| if d { "->" } else { "" })
+        .unwrap_or("")
+        .bright_green(),
+      v = v.green(),
+      installed = if self.installed.unwrap_or(false) {
+        "✓"
+      } else {
+        ""
+      }
+      .bright_green(),
+      npm = npm.bright_green(),
+      ssl = ssl.bright_black(),
+      died_on = if eol {
+        died_on.bright_red()
+      } else {
+        died_on.bright_black()
+      },
+      nike_name = lts.bright_green()
+    )
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduleMetadata {
+  pub start: String,
+  pub end: String,
+  pub maintenance: Option<String>,
+  pub lts: Option<String>,
+  pub codename: Option<String>,
+  pub version: Option<String>,
+}
+
+impl ScheduleMetadata {
+  pub async fn fetch(
+    snm_config: &SnmConfig,
+    refresh: bool,
+  ) -> anyhow::Result<HashMap<String, ScheduleMetadata>> {
+    let url = format!(
+      "{host}/nodejs/Release/main/schedule.json",
+      host = snm_config.node_github_resource_host
+    );
+
+    MetadataCache::new(snm_config, refresh)
+      .get_json("schedule.json", &url)
+      .await
+  }
+}
+
+impl NodeMetadata {
+  pub fn semver(&self) -> Option<semver::Version> {
+    semver::Version::parse(self.version.trim_start_matches('v')).ok()
+  }
+
+  /// 大版本停止维护的日期，schedule.json 中没有该大版本或日期无法解析时为 `None`
+  pub fn end_of_life(&self) -> Option<NaiveDate> {
+    let schedule = self.schedule.as_ref()?;
+    NaiveDate::parse_from_str(&schedule.end, "%Y-%m-%d").ok()
+  }
+
+  /// 在 `today` 时是否已经停止维护，没有维护计划的版本视为未停止
+  pub fn is_eol(&self, today: NaiveDate) -> bool {
+    self.end_of_life().is_some_and(|end| today > end)
+  }
+
+  /// 按 `SnmConfig::node_dist_urls` 的顺序获取 index.json，前面的镜像失败时换下一个
+  ///
+  /// 每个镜像单独缓存，任意镜像有未过期的缓存时直接使用，不再请求已经失败过的镜像
+  pub async fn fetch(snm_config: &SnmConfig, refresh: bool) -> anyhow::Result<Vec<NodeMetadata>> {
+    let cache = MetadataCache::new(snm_config, refresh);
+
+    let mirrors = snm_config
+      .node_dist_urls
+      .iter()
+      .map(|host| {
+        (
+          Self::cache_name(host),
+          format!("{host}/index.json", host = host),
+        )
+      })
+      .collect::<Vec<_>>();
+
+    if let Some(value) = mirrors
+      .iter()
+      .find_map(|(name, url)| cache.get_fresh_json(name, url))
+    {
+      return Ok(value);
+    }
+
+    let mut errors = vec![];
+
+    for (name, url) in &mirrors {
+      match cache.get_json(name, url).await {
+        Ok(value) => return Ok(value),
+        Err(e) if snm_config.node_dist_urls.len() == 1 => return Err(e),
+        Err(e) => {
+          trace!("Failed to fetch {}: {:?}", url, e);
+          errors.push(format!("  {} : {}", url, e));
+        }
+      }
+    }
+
+    anyhow::bail!(
+      "Failed to fetch index.json from every mirror:\n{}",
+      errors.join("\n")
+    )
+  }
+
+  /// 镜像对应的缓存文件名，例如 `https://nodejs.org/dist` 对应 `index.https---nodejs-org-dist.json`
+  fn cache_name(host: &str) -> String {
+    let slug = host
+      .trim_end_matches('/')
+      .chars()
+      .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+      .collect::<String>();
+
+    format!("index.{}.json", slug)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use snm_test_utils::SnmTestContext;
+  use test_context::test_context;
+  use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path},
+  };
+
+  use super::*;
+
+  #[test]
+  fn should_display_without_schedule() -> anyhow::Result<()> {
+    let mut node: NodeMetadata = serde_json::from_str(
+      r#"{"version":"v23.11.0","date":"2025-04-01","npm":"10.9.2","v8":"12.4","lts":false,"security":false}"#,
+    )?;
+
+    assert!(node.end_of_life().is_none());
+    assert!(!node.is_eol(Utc::now().date_naive()));
+    assert!(node.to_string().contains("23.11.0"));
+
+    node.schedule = Some(ScheduleMetadata {
+      start: String::from("2024-10-16"),
+      end: String::from("2025-06-01"),
+      maintenance: None,
+      lts: None,
+      codename: None,
+      version: None,
+    });
+
+    assert!(!node.is_eol(NaiveDate::from_ymd_opt(2025, 6, 1).unwrap()));
+    assert!(node.is_eol(NaiveDate::from_ymd_opt(2025, 6, 2).unwrap()));
+
+    Ok(())
+  }
+
+  #[test_context(SnmTestContext)]
+  #[tokio::test]
+  async fn should_fall_back_to_next_mirror(ctx: &mut SnmTestContext) -> anyhow::Result<()> {
+    let broken = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path("/index.json"))
+      .respond_with(ResponseTemplate::new(404))
+      .expect(1)
+      .mount(&broken)
+      .await;
+
+    let healthy = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path("/index.json"))
+      .respond_with(ResponseTemplate::new(200).set_body_string(
+        r#"[{"version":"v20.11.0","date":"2024-01-09","npm":"10.2.4","v8":"11.3","lts":"Iron","security":false}]"#,
+      ))
+      .expect(1)
+      .mount(&healthy)
+      .await;
+
+    ctx.set_envs(&[
+      (
+        format!("{}_HOME_DIR", ctx.get_id()),
+        ctx.get_temp_dir().to_string_lossy().to_string(),
+      ),
+      (
+        format!("{}_NODE_DIST_URL", ctx.get_id()),
+        format!("{},{}", broken.uri(), healthy.uri()),
+      ),
+    ]);
+
+    let config = SnmConfig::from(ctx.get_id(), ctx.get_temp_dir())?;
+
+    let remote = NodeMetadata::fetch(&config, false).await?;
+
+    assert_eq!(remote.len(), 1);
+    assert_eq!(remote[0].version, "v20.11.0");
+
+    // 备用镜像的缓存未过期，不再请求任何镜像
+    let cached = NodeMetadata::fetch(&config, false).await?;
+    assert_eq!(cached[0].version, "v20.11.0");
+
+    Ok(())
+  }
+}
@@ -1,12 +1,16 @@
 use std::path::PathBuf;
 
 use anyhow::bail;
-use snm_config::snm_config::SnmConfig;
+use semver::Version;
+use serde::Serialize;
+use snm_config::{platform::Platform, snm_config::SnmConfig};
 use snm_downloader::{DownloadNodeResource, download_resource};
-use snm_utils::consts::NODE_VERSION_FILE_NAME;
-use up_finder::UpFinder;
+use tracing::trace;
 
-use crate::{NodeVersion, NodeVersionHome};
+use crate::{
+  NodeAlias, NodeMetadata, NodeVersion, NodeVersionFile, NodeVersionHome, NodeVersionPolicy,
+  NodeVersionReq,
+};
 
 #[derive(Debug)]
 pub struct NodeResolver {
@@ -19,41 +23,176 @@ impl From<SnmConfig> for NodeResolver {
   }
 }
 
+/// 一次 Node.js 版本解析的结果，记录了版本是从哪里来的，供 `snm which` 展示
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeResolution {
+  pub version: String,
+  /// 版本声明文件中的原始内容，例如 `lts/iron`、`^20`
+  pub requested: Option<String>,
+  /// 版本声明的来源类型，例如 `nvmrc`
+  pub source: Option<String>,
+  /// 决定版本的文件，使用 default 时为 `None`
+  pub file: Option<PathBuf>,
+  /// 项目中没有声明版本，使用了 `snm node default` 设置的默认版本
+  pub used_default: bool,
+  pub bin_dir: PathBuf,
+  pub exe: PathBuf,
+  pub installed: bool,
+}
+
 impl NodeResolver {
   pub async fn resolve_node_bin_dir(&self) -> anyhow::Result<PathBuf> {
-    let nv = self.resolve_node_version()?;
+    Ok(self.resolve_node().await?.bin_dir)
+  }
+
+  /// 解析版本、检查版本策略，并确保对应的 Node.js 已经安装
+  pub async fn resolve_node(&self) -> anyhow::Result<NodeResolution> {
+    let mut resolution = self.resolve().await?;
+
+    let nv = NodeVersion::try_from(resolution.version.clone())?;
 
-    let node_home_dir = self.config.node_bin_dir.join(&nv.val);
+    NodeVersionPolicy::from_config(&self.config)?.check(&nv)?;
 
-    let node_home_dir = NodeVersionHome(node_home_dir);
+    self.ensure_node(&resolution.exe, &nv).await?;
 
-    let node_exe = node_home_dir.exe();
+    resolution.installed = true;
+
+    Ok(resolution)
+  }
+
+  /// 只解析版本，不检查版本策略，也不会安装
+  pub async fn resolve(&self) -> anyhow::Result<NodeResolution> {
+    let file = self.find_up_node_version()?;
+
+    let nv = match &file {
+      Some(file) => {
+        let req = NodeVersionReq::try_from(file.raw.clone()).map_err(|e| {
+          anyhow::anyhow!("Failed to parse Node version from {:?}: {:?}", file.path, e)
+        })?;
+        self.resolve_version_req(&req).await?
+      }
+      None => self.find_default_node_version()?,
+    };
 
-    self.ensure_node(&node_exe, &nv).await?;
+    let node_home_dir = NodeVersionHome(self.config.node_bin_dir.join(&nv.val));
 
-    let node_bin_dir = node_home_dir.bin_dir();
+    let exe = node_home_dir.exe();
 
-    Ok(node_bin_dir)
+    Ok(NodeResolution {
+      installed: exe.try_exists()?,
+      used_default: file.is_none(),
+      requested: file.as_ref().map(|file| file.raw.clone()),
+      source: file.as_ref().map(|file| file.source.to_string()),
+      file: file.map(|file| file.path),
+      bin_dir: node_home_dir.bin_dir(),
+      exe,
+      version: nv.val,
+    })
   }
 }
 
 impl NodeResolver {
-  fn resolve_node_version(&self) -> anyhow::Result<NodeVersion> {
-    let nv = if let Some(nv) = self.find_up_node_version()? {
-      nv
-    } else {
-      self.find_default_node_version()?
+  /// 把版本要求解析成具体版本：精确版本直接返回，范围优先匹配本地已安装的最高版本，
+  /// 关键字以及本地没有匹配的范围再去 index.json 中查找
+  pub async fn resolve_version_req(&self, req: &NodeVersionReq) -> anyhow::Result<NodeVersion> {
+    match req {
+      NodeVersionReq::Exact(nv) => return Ok(nv.clone()),
+      NodeVersionReq::Alias { raw } => return self.resolve_alias(raw),
+      _ => {}
+    }
+
+    if !req.is_keyword() {
+      let installed = self.list_installed_versions()?;
+
+      if let Some(version) = req.max_satisfying(&installed) {
+        trace!(
+          "Resolved Node.js version {:?} to installed version {}",
+          req.raw(),
+          version
+        );
+        return NodeVersion::try_from(version.to_string());
+      }
+    }
+
+    self.resolve_remote_version_req(req).await
+  }
+
+  /// 只对照 index.json 解析，安装时总是取最新的匹配版本
+  pub async fn resolve_remote_version_req(
+    &self,
+    req: &NodeVersionReq,
+  ) -> anyhow::Result<NodeVersion> {
+    match req {
+      NodeVersionReq::Exact(nv) => return Ok(nv.clone()),
+      NodeVersionReq::Alias { raw } => return self.resolve_alias(raw),
+      _ => {}
+    }
+
+    let remote = NodeMetadata::fetch(&self.config, false).await?;
+
+    let Some(version) = req.max_satisfying_metadata(&remote) else {
+      bail!("No Node.js version matches: {}", req.raw());
+    };
+
+    trace!(
+      "Resolved Node.js version {:?} to remote version {}",
+      req.raw(),
+      version
+    );
+
+    NodeVersion::try_from(version.to_string())
+  }
+
+  fn resolve_alias(&self, name: &str) -> anyhow::Result<NodeVersion> {
+    let Some(nv) = NodeAlias::new(&self.config).get(name)? else {
+      bail!(
+        "Unknown Node.js version or alias: {}, create it with `snm node alias {} <version>`",
+        name,
+        name
+      );
     };
+
+    trace!("Resolved Node.js alias {:?} to version {}", name, nv.val);
+
     Ok(nv)
   }
 
+  fn list_installed_versions(&self) -> anyhow::Result<Vec<Version>> {
+    let versions = self
+      .config
+      .node_bin_dir
+      .read_dir()?
+      .filter_map(|entry| entry.ok())
+      .filter(|entry| entry.path().is_dir())
+      .filter_map(|entry| Version::parse(&entry.file_name().to_string_lossy()).ok())
+      .filter(|version| {
+        NodeVersionHome(self.config.node_bin_dir.join(version.to_string()))
+          .exe()
+          .exists()
+      })
+      .collect();
+
+    Ok(versions)
+  }
+
   async fn ensure_node(&self, node_exe: &PathBuf, nv: &NodeVersion) -> anyhow::Result<()> {
     if !node_exe
       .try_exists()
       .map_err(|e| anyhow::anyhow!("Failed to check if node executable exists: {:?}", e))?
     {
+      self.config.check_auto_install(
+        &format!("Node.js v{}", nv.val),
+        &format!("snm node install {}", nv.val),
+      )?;
+
+      // shim 只能执行本机平台的 Node.js，忽略 SNM_PLATFORM_OS / SNM_PLATFORM_ARCH / SNM_PLATFORM_LIBC
+      let config = SnmConfig {
+        platform: Platform::default(),
+        ..self.config.clone()
+      };
+
       let resource = DownloadNodeResource::builder()
-        .config(&self.config)
+        .config(&config)
         .bin_name(String::from("node"))
         .version(nv.val.clone())
         .build();
@@ -63,24 +202,33 @@ impl NodeResolver {
     Ok(())
   }
 
-  fn find_up_node_version(&self) -> anyhow::Result<Option<NodeVersion>> {
-    let find_up = UpFinder::builder()
-      .cwd(&self.config.workspace) // 从当前目录开始
-      .build();
-
-    let files = find_up.find_up(NODE_VERSION_FILE_NAME);
+  /// 按 `SnmConfig::node_version_sources` 的优先级向上查找项目声明的 Node.js 版本
+  pub fn find_up_node_version(&self) -> anyhow::Result<Option<NodeVersionFile>> {
+    let file = NodeVersionFile::find_up(&self.config.workspace, &self.config.node_version_sources)?;
+
+    let Some(file) = file else {
+      if self.config.strict {
+        let expect = self
+          .config
+          .node_version_sources
+          .iter()
+          .map(|source| source.to_string())
+          .collect::<Vec<_>>()
+          .join(", ");
+        bail!(
+          "In strict mode, a Node.js version must be configured in the current directory ( sources: {} ).",
+          expect
+        );
+      }
+      return Ok(None);
+    };
 
-    if files.is_empty() && self.config.strict {
-      bail!("In strict mode, a .node-version file must be configured in the current directory.");
-    }
+    trace!(
+      "Found Node.js version {:?} from {} ( {:?} )",
+      file.raw, file.source, file.path
+    );
 
-    if let Some(file) = files.first() {
-      let nv = NodeVersion::try_from(file.to_owned())
-        .map_err(|e| anyhow::anyhow!("Failed to parse Node version from file: {:?}", e))?;
-      Ok(Some(nv))
-    } else {
-      Ok(None)
-    }
+    Ok(Some(file))
   }
 
   fn find_default_node_version(&self) -> anyhow::Result<NodeVersion> {
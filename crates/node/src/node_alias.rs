@@ -0,0 +1,176 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use anyhow::bail;
+use snm_config::snm_config::SnmConfig;
+
+use crate::{NodeVersion, NodeVersionReq};
+
+/// 内置的别名，对应 node_bin_dir 下的 default 软链
+pub const DEFAULT_ALIAS: &str = "default";
+
+/// 用户自定义的 Node.js 版本别名
+///
+/// 和 `default` 一样，每个别名都是一个软链，指向 node_bin_dir 下以精确版本命名的目录。
+/// 目标版本不要求已经安装，解析时只读取软链指向的目录名。
+pub struct NodeAlias<'a> {
+  config: &'a SnmConfig,
+}
+
+impl<'a> NodeAlias<'a> {
+  pub fn new(config: &'a SnmConfig) -> Self {
+    Self { config }
+  }
+
+  fn link_path(&self, name: &str) -> PathBuf {
+    if name == DEFAULT_ALIAS {
+      self.config.node_bin_dir.join(DEFAULT_ALIAS)
+    } else {
+      self.config.node_alias_dir.join(name)
+    }
+  }
+
+  fn link_exists(path: &Path) -> bool {
+    path.symlink_metadata().is_ok()
+  }
+
+  /// 读取别名指向的精确版本，别名不存在时返回 `None`
+  pub fn get(&self, name: &str) -> anyhow::Result<Option<NodeVersion>> {
+    let path = self.link_path(name);
+
+    if !Self::link_exists(&path) {
+      return Ok(None);
+    }
+
+    let version = path
+      .read_link()
+      .map_err(|e| anyhow::anyhow!("Failed to read alias {}: {:?}", name, e))?
+      .file_name()
+      .map(|s| s.to_string_lossy().into_owned())
+      .ok_or_else(|| anyhow::anyhow!("Invalid symbolic link for alias {}", name))?;
+
+    NodeVersion::try_from(version).map(Some)
+  }
+
+  pub fn set(&self, name: &str, version: &NodeVersion) -> anyhow::Result<()> {
+    if name == DEFAULT_ALIAS {
+      bail!("Use `snm node default` to change the default Node.js version");
+    }
+
+    if !NodeVersionReq::is_alias_name(name) {
+      bail!(
+        "Invalid alias name: {}, it must start with a letter, contain only letters, digits, '-', '_' or '.', and must not be a version or keyword",
+        name
+      );
+    }
+
+    let path = self.link_path(name);
+
+    if Self::link_exists(&path) {
+      fs::remove_file(&path)?;
+    }
+
+    let target = self.config.node_bin_dir.join(&version.val);
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&target, &path)?;
+
+    #[cfg(windows)]
+    std::os::windows::fs::symlink_dir(&target, &path)?;
+
+    Ok(())
+  }
+
+  /// 删除别名，返回别名此前是否存在
+  pub fn remove(&self, name: &str) -> anyhow::Result<bool> {
+    if name == DEFAULT_ALIAS {
+      bail!("The default alias can not be removed, use `snm node default` instead");
+    }
+
+    let path = self.link_path(name);
+
+    if !Self::link_exists(&path) {
+      return Ok(false);
+    }
+
+    #[cfg(unix)]
+    fs::remove_file(&path)?;
+
+    #[cfg(windows)]
+    fs::remove_dir(&path)?;
+
+    Ok(true)
+  }
+
+  /// 列出所有别名，按名字排序，`default` 不包含在内
+  pub fn list(&self) -> anyhow::Result<Vec<(String, NodeVersion)>> {
+    let mut aliases = self
+      .config
+      .node_alias_dir
+      .read_dir()?
+      .filter_map(|entry| entry.ok())
+      .filter_map(|entry| {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let version = self.get(&name).ok().flatten()?;
+        Some((name, version))
+      })
+      .collect::<Vec<_>>();
+
+    aliases.sort_by(|a, b| a.0.cmp(&b.0));
+
+    Ok(aliases)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use snm_test_utils::SnmTestContext;
+  use test_context::test_context;
+
+  use super::*;
+
+  #[test_context(SnmTestContext)]
+  #[tokio::test]
+  async fn should_manage_aliases(ctx: &mut SnmTestContext) -> anyhow::Result<()> {
+    ctx.set_envs(&[(
+      format!("{}_HOME_DIR", ctx.get_id()),
+      ctx.get_temp_dir().to_string_lossy().to_string(),
+    )]);
+
+    let config = SnmConfig::from(ctx.get_id(), ctx.get_temp_dir())?;
+    let alias = NodeAlias::new(&config);
+
+    let v20 = NodeVersion::try_from(String::from("20.11.0"))?;
+    let v18 = NodeVersion::try_from(String::from("18.19.0"))?;
+
+    alias.set("work", &v20)?;
+    alias.set("legacy", &v18)?;
+    alias.set("work", &v18)?;
+
+    let list = alias
+      .list()?
+      .into_iter()
+      .map(|(name, nv)| (name, nv.val))
+      .collect::<Vec<_>>();
+
+    assert_eq!(
+      list,
+      vec![
+        ("legacy".to_string(), "18.19.0".to_string()),
+        ("work".to_string(), "18.19.0".to_string())
+      ]
+    );
+
+    assert!(alias.remove("work")?);
+    assert!(!alias.remove("work")?);
+    assert!(alias.get("work")?.is_none());
+
+    assert!(alias.set("default", &v20).is_err());
+    assert!(alias.set("lts/*", &v20).is_err());
+    assert!(alias.set("20", &v20).is_err());
+
+    Ok(())
+  }
+}
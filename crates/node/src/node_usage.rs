@@ -0,0 +1,183 @@
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path, PathBuf},
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use snm_config::snm_config::SnmConfig;
+use snm_utils::write::write_atomic;
+
+/// 单个 Node.js 版本的使用记录
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NodeUsageRecord {
+  /// 最近一次被 shim 使用的时间（秒级时间戳）
+  pub last_used: u64,
+  /// 使用过该版本的项目目录（版本声明文件所在目录）及其最近使用时间
+  #[serde(default)]
+  pub projects: BTreeMap<PathBuf, u64>,
+}
+
+/// 记录的精度，`snm node prune` 按天判断，同一版本、同一项目一天内只写一次
+const RECORD_INTERVAL_SECS: u64 = 24 * 60 * 60;
+
+/// shim 每次解析出 Node.js 版本后记录的使用情况，`snm node prune` 依赖它判断哪些版本可以删除
+///
+/// 所有版本的记录保存在 `SnmConfig::node_usage_file` 中，key 为精确版本号
+pub struct NodeUsage<'a> {
+  config: &'a SnmConfig,
+}
+
+impl<'a> NodeUsage<'a> {
+  pub fn new(config: &'a SnmConfig) -> Self {
+    Self { config }
+  }
+
+  pub fn now() -> u64 {
+    SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.as_secs())
+      .unwrap_or_default()
+  }
+
+  /// 读取所有记录，文件不存在或损坏时视为没有记录
+  pub fn load(&self) -> BTreeMap<String, NodeUsageRecord> {
+    fs::read(&self.config.node_usage_file)
+      .ok()
+      .and_then(|content| serde_json::from_slice(&content).ok())
+      .unwrap_or_default()
+  }
+
+  fn save(&self, records: &BTreeMap<String, NodeUsageRecord>) -> anyhow::Result<()> {
+    // 先写临时文件再重命名，避免多个 shim 同时写入时读到半截内容
+    write_atomic(
+      &self.config.node_usage_file,
+      serde_json::to_vec_pretty(records)?,
+    )
+  }
+
+  /// 记录一次使用，`project` 为版本声明文件所在目录，使用 default 时为 `None`
+  ///
+  /// 已有的记录不到 `RECORD_INTERVAL_SECS` 时不写入，避免并行的 shim（例如 `npm-run-all`）反复读写同一个文件
+  pub fn record(&self, version: &str, project: Option<&Path>) -> anyhow::Result<()> {
+    let now = Self::now();
+
+    let mut records = self.load();
+
+    let is_fresh = |time: u64| now.saturating_sub(time) < RECORD_INTERVAL_SECS;
+
+    if let Some(record) = records.get(version) {
+      let project_is_fresh = project.is_none_or(|project| {
+        record
+          .projects
+          .get(project)
+          .is_some_and(|time| is_fresh(*time))
+      });
+
+      if is_fresh(record.last_used) && project_is_fresh {
+        return Ok(());
+      }
+    }
+
+    let record = records.entry(version.to_string()).or_default();
+
+    record.last_used = now;
+
+    if let Some(project) = project {
+      record.projects.insert(project.to_path_buf(), now);
+    }
+
+    self.save(&records)
+  }
+
+  /// 删除某个版本的记录
+  pub fn remove(&self, version: &str) -> anyhow::Result<()> {
+    let mut records = self.load();
+
+    if records.remove(version).is_some() {
+      self.save(&records)?;
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use snm_test_utils::SnmTestContext;
+  use test_context::test_context;
+
+  use super::*;
+
+  #[test_context(SnmTestContext)]
+  #[tokio::test]
+  async fn should_record_usage(ctx: &mut SnmTestContext) -> anyhow::Result<()> {
+    ctx.set_envs(&[(
+      format!("{}_HOME_DIR", ctx.get_id()),
+      ctx.get_temp_dir().to_string_lossy().to_string(),
+    )]);
+
+    let config = SnmConfig::from(ctx.get_id(), ctx.get_temp_dir())?;
+    let usage = NodeUsage::new(&config);
+
+    assert!(usage.load().is_empty());
+
+    let project = ctx.get_temp_dir().join("app");
+
+    usage.record("20.11.0", Some(&project))?;
+    usage.record("20.11.0", None)?;
+    usage.record("18.19.0", None)?;
+
+    let records = usage.load();
+    assert_eq!(records.len(), 2);
+    assert_eq!(
+      records["20.11.0"].projects.keys().collect::<Vec<_>>(),
+      vec![&project]
+    );
+    assert!(records["18.19.0"].projects.is_empty());
+
+    usage.remove("18.19.0")?;
+    assert!(!usage.load().contains_key("18.19.0"));
+
+    Ok(())
+  }
+
+  #[test_context(SnmTestContext)]
+  #[tokio::test]
+  async fn should_skip_recent_records(ctx: &mut SnmTestContext) -> anyhow::Result<()> {
+    ctx.set_envs(&[(
+      format!("{}_HOME_DIR", ctx.get_id()),
+      ctx.get_temp_dir().to_string_lossy().to_string(),
+    )]);
+
+    let config = SnmConfig::from(ctx.get_id(), ctx.get_temp_dir())?;
+    let usage = NodeUsage::new(&config);
+    let project = ctx.get_temp_dir().join("app");
+
+    let write = |last_used: u64| -> anyhow::Result<()> {
+      let record = NodeUsageRecord {
+        last_used,
+        projects: BTreeMap::from([(project.clone(), last_used)]),
+      };
+      usage.save(&BTreeMap::from([(String::from("20.11.0"), record)]))
+    };
+
+    let recent = NodeUsage::now() - 60;
+    write(recent)?;
+    usage.record("20.11.0", Some(&project))?;
+    assert_eq!(usage.load()["20.11.0"].last_used, recent);
+
+    // 新项目即使版本的记录很新也要写入
+    let other = ctx.get_temp_dir().join("other");
+    usage.record("20.11.0", Some(&other))?;
+    assert!(usage.load()["20.11.0"].projects.contains_key(&other));
+
+    let stale = NodeUsage::now() - RECORD_INTERVAL_SECS - 60;
+    write(stale)?;
+    usage.record("20.11.0", None)?;
+    assert!(usage.load()["20.11.0"].last_used > stale);
+
+    Ok(())
+  }
+}
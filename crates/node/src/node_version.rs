@@ -1,8 +1,14 @@
-use std::{fs::read_to_string, path::PathBuf};
+use std::{
+  fs::read_to_string,
+  path::{Path, PathBuf},
+};
 
 use anyhow::bail;
 use lazy_regex::regex;
+use snm_downloader::NodeChannel;
 
+/// 精确的 Node.js 版本，除正式版外也支持 rc、nightly、test、v8-canary 渠道的预发布版本，
+/// 例如 `23.0.0-rc.2`、`24.0.0-nightly20250101ab12cd34ef`
 #[derive(Debug, Clone)]
 pub struct NodeVersion {
   pub raw: Option<String>,
@@ -14,7 +20,9 @@ impl TryFrom<String> for NodeVersion {
   fn try_from(raw: String) -> Result<Self, Self::Error> {
     let raw_trim = raw.trim().to_lowercase();
 
-    let r = regex!(r"^v?(0|[1-9]\d*)\.(0|[1-9]\d*)\.(0|[1-9]\d*)$");
+    let r = regex!(
+      r"^v?(0|[1-9]\d*)\.(0|[1-9]\d*)\.(0|[1-9]\d*)(-(rc\.\d+|nightly\d{8}[0-9a-f]+|test\d{8}[0-9a-f]*|v8-canary\d{8}[0-9a-f]+))?$"
+    );
 
     if !r.is_match(&raw_trim) {
       bail!("Invalid Node.js version format: {}", raw);
@@ -29,6 +37,24 @@ impl TryFrom<String> for NodeVersion {
   }
 }
 
+impl NodeVersion {
+  pub fn channel(&self) -> NodeChannel {
+    NodeChannel::from_version(&self.val).unwrap_or(NodeChannel::Release)
+  }
+
+  /// 从 `node-v20.11.0-linux-x64.tar.xz`、`node-v23.0.0-rc.2-win-x64.zip` 这样的归档名中取出版本号
+  pub fn from_archive_name(archive: &Path) -> Option<Self> {
+    let name = archive.file_name()?.to_str()?;
+
+    let parts = name.strip_prefix("node-v")?.split('-').collect::<Vec<_>>();
+
+    // 预发布版本号自身也带 `-`，从长到短依次尝试
+    (1..=parts.len())
+      .rev()
+      .find_map(|len| Self::try_from(parts[..len].join("-")).ok())
+  }
+}
+
 impl TryFrom<PathBuf> for NodeVersion {
   type Error = anyhow::Error;
   fn try_from(file: PathBuf) -> Result<Self, Self::Error> {
@@ -42,6 +68,66 @@ mod test {
   use anyhow::bail;
   use lazy_regex::regex;
 
+  use super::*;
+
+  #[test]
+  fn should_parse_version_from_archive_name() {
+    let cases = [
+      ("node-v20.11.0-linux-x64.tar.xz", Some("20.11.0")),
+      ("node-v23.0.0-rc.2-win-x64.zip", Some("23.0.0-rc.2")),
+      ("/tmp/node-v18.19.0-darwin-arm64.tar.gz", Some("18.19.0")),
+      ("node-v20.11.0.tar.gz", None),
+      ("patched-node.tar.xz", None),
+    ];
+
+    for (name, expected) in cases {
+      let version = NodeVersion::from_archive_name(Path::new(name)).map(|v| v.val);
+      assert_eq!(version.as_deref(), expected, "{}", name);
+    }
+  }
+
+  #[test]
+  fn should_parse_prerelease_channels() -> anyhow::Result<()> {
+    let cases = [
+      ("v20.11.0", "20.11.0", NodeChannel::Release),
+      ("v23.0.0-rc.2", "23.0.0-rc.2", NodeChannel::Rc),
+      (
+        "24.0.0-nightly20250101ab12cd34ef",
+        "24.0.0-nightly20250101ab12cd34ef",
+        NodeChannel::Nightly,
+      ),
+      (
+        "v21.0.0-test20230901ab12cd34ef",
+        "21.0.0-test20230901ab12cd34ef",
+        NodeChannel::Test,
+      ),
+      (
+        "v22.0.0-v8-canary20231127ab12cd34ef",
+        "22.0.0-v8-canary20231127ab12cd34ef",
+        NodeChannel::V8Canary,
+      ),
+    ];
+
+    for (raw, val, channel) in cases {
+      let nv = NodeVersion::try_from(raw.to_string())?;
+      assert_eq!(nv.val, val);
+      assert_eq!(nv.channel(), channel);
+    }
+
+    assert!(NodeVersion::try_from(String::from("20.0.0-beta.1")).is_err());
+
+    assert_eq!(
+      NodeChannel::Rc.dist_url("https://nodejs.org/dist/"),
+      "https://nodejs.org/download/rc"
+    );
+    assert_eq!(
+      NodeChannel::Release.dist_url("https://nodejs.org/dist"),
+      "https://nodejs.org/dist"
+    );
+
+    Ok(())
+  }
+
   #[test]
   fn should_xx() -> anyhow::Result<()> {
     let raw = r#"v20.0.0
@@ -0,0 +1,188 @@
+use std::{fs::read_to_string, path::Path, path::PathBuf};
+
+use snm_config::node_version_source::NodeVersionSource;
+
+/// 找到的 Node.js 版本声明，记录了来源类型和所在文件
+#[derive(Debug, Clone)]
+pub struct NodeVersionFile {
+  pub source: NodeVersionSource,
+  pub path: PathBuf,
+  pub raw: String,
+}
+
+impl NodeVersionFile {
+  /// 按来源优先级读取某个目录下的版本声明
+  pub fn find_in(dir: &Path, sources: &[NodeVersionSource]) -> anyhow::Result<Option<Self>> {
+    for source in sources {
+      let path = dir.join(source.file_name());
+
+      if !path.is_file() {
+        continue;
+      }
+
+      if let Some(raw) = Self::read(source, &path)? {
+        return Ok(Some(Self {
+          source: *source,
+          path,
+          raw,
+        }));
+      }
+    }
+
+    Ok(None)
+  }
+
+  /// 从当前目录开始逐级向上查找，离得最近的目录优先
+  ///
+  /// 先在所有层级查找版本文件，都没有时才查找 package.json 中的 `volta.node`、`engines.node`
+  pub fn find_up(cwd: &Path, sources: &[NodeVersionSource]) -> anyhow::Result<Option<Self>> {
+    let (package_json, files): (Vec<_>, Vec<_>) =
+      sources.iter().partition(|source| source.is_package_json());
+
+    for sources in [files, package_json] {
+      for dir in cwd.ancestors() {
+        if let Some(file) = Self::find_in(dir, &sources)? {
+          return Ok(Some(file));
+        }
+      }
+    }
+
+    Ok(None)
+  }
+
+  fn read(source: &NodeVersionSource, path: &Path) -> anyhow::Result<Option<String>> {
+    let content = read_to_string(path)?;
+
+    let raw = match source {
+      NodeVersionSource::NodeVersion => Some(content.trim().to_string()),
+      NodeVersionSource::Nvmrc => content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .find(|line| !line.is_empty())
+        .map(|line| line.to_string()),
+      NodeVersionSource::ToolVersions => content.lines().find_map(|line| {
+        let mut parts = line
+          .split('#')
+          .next()
+          .unwrap_or_default()
+          .split_whitespace();
+        match parts.next() {
+          Some("nodejs" | "node") => parts.next().map(|v| v.to_string()),
+          _ => None,
+        }
+      }),
+      NodeVersionSource::Volta => Self::read_package_json(&content, &["volta", "node"]),
+      NodeVersionSource::Engines => Self::read_package_json(&content, &["engines", "node"]),
+    };
+
+    Ok(raw.filter(|raw| !raw.is_empty()))
+  }
+
+  fn read_package_json(content: &str, keys: &[&str]) -> Option<String> {
+    let json = serde_json::from_str::<serde_json::Value>(content).ok()?;
+
+    keys
+      .iter()
+      .try_fold(&json, |value, key| value.get(key))
+      .and_then(|value| value.as_str())
+      .map(|value| value.trim().to_string())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+
+  use super::*;
+
+  #[test]
+  fn should_read_every_source() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let dir = tmp.path();
+
+    fs::write(dir.join(".nvmrc"), "# comment\nv18.19.0 # pinned\n")?;
+    fs::write(
+      dir.join(".tool-versions"),
+      "python 3.12.0\nnodejs 20.11.0\n",
+    )?;
+    fs::write(
+      dir.join("package.json"),
+      r#"{"volta":{"node":"16.20.2"},"engines":{"node":">=18"}}"#,
+    )?;
+
+    let cases = [
+      (NodeVersionSource::Nvmrc, "v18.19.0"),
+      (NodeVersionSource::ToolVersions, "20.11.0"),
+      (NodeVersionSource::Volta, "16.20.2"),
+      (NodeVersionSource::Engines, ">=18"),
+    ];
+
+    for (source, expect) in cases {
+      let file = NodeVersionFile::find_in(dir, &[source])?.unwrap();
+      assert_eq!(file.source, source);
+      assert_eq!(file.raw, expect);
+    }
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_follow_precedence_and_prefer_nearest_dir() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let dir = tmp.path();
+    let nested = dir.join("packages").join("app");
+    fs::create_dir_all(&nested)?;
+
+    fs::write(dir.join(".node-version"), "20.11.0")?;
+    fs::write(dir.join(".nvmrc"), "18.19.0")?;
+    fs::write(nested.join("package.json"), r#"{"name":"app"}"#)?;
+
+    let sources = NodeVersionSource::DEFAULT_PRECEDENCE;
+
+    let file = NodeVersionFile::find_up(&nested, &sources)?.unwrap();
+    assert_eq!(file.source, NodeVersionSource::NodeVersion);
+    assert_eq!(file.path, dir.join(".node-version"));
+
+    let file = NodeVersionFile::find_up(
+      &nested,
+      &[NodeVersionSource::Nvmrc, NodeVersionSource::NodeVersion],
+    )?
+    .unwrap();
+    assert_eq!(file.source, NodeVersionSource::Nvmrc);
+
+    fs::write(dir.join("package.json"), r#"{"volta":{"node":"16.20.2"}}"#)?;
+    fs::write(nested.join("package.json"), r#"{"engines":{"node":"^18"}}"#)?;
+    fs::remove_file(dir.join(".node-version"))?;
+    fs::remove_file(dir.join(".nvmrc"))?;
+    let file = NodeVersionFile::find_up(&nested, &sources)?.unwrap();
+    assert_eq!(file.source, NodeVersionSource::Engines);
+    assert_eq!(file.raw, "^18");
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_prefer_root_version_file_over_nested_engines() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let dir = tmp.path();
+    let nested = dir.join("packages").join("app");
+    let dep = dir.join("node_modules").join("dep");
+    fs::create_dir_all(&nested)?;
+    fs::create_dir_all(&dep)?;
+
+    fs::write(dir.join(".node-version"), "20.11.0")?;
+    fs::write(
+      nested.join("package.json"),
+      r#"{"engines":{"node":">=16"}}"#,
+    )?;
+    fs::write(dep.join("package.json"), r#"{"volta":{"node":"14.21.3"}}"#)?;
+
+    for cwd in [&nested, &dep] {
+      let file = NodeVersionFile::find_up(cwd, &NodeVersionSource::DEFAULT_PRECEDENCE)?.unwrap();
+      assert_eq!(file.source, NodeVersionSource::NodeVersion);
+      assert_eq!(file.raw, "20.11.0");
+    }
+
+    Ok(())
+  }
+}
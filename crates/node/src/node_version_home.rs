@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{fs, path::PathBuf};
 
 pub struct NodeVersionHome(pub PathBuf);
 
@@ -20,4 +20,92 @@ impl NodeVersionHome {
       dir.join("bin")
     }
   }
+
+  /// `npm install -g` 的安装目录
+  pub fn global_modules_dir(&self) -> PathBuf {
+    let dir = &self.0;
+    if cfg!(windows) {
+      dir.join("node_modules")
+    } else {
+      dir.join("lib").join("node_modules")
+    }
+  }
+
+  /// 全局安装的包，返回 `name@version`，不包含 Node.js 自带的 npm、corepack 以及 `npm link` 的包
+  pub fn global_packages(&self) -> anyhow::Result<Vec<String>> {
+    let root = self.global_modules_dir();
+
+    if !root.try_exists()? {
+      return Ok(vec![]);
+    }
+
+    let mut dirs = vec![];
+
+    for entry in root.read_dir()?.filter_map(|entry| entry.ok()) {
+      let name = entry.file_name().to_string_lossy().into_owned();
+      if name.starts_with('@') {
+        dirs.extend(
+          entry
+            .path()
+            .read_dir()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path()),
+        );
+      } else if !name.starts_with('.') {
+        dirs.push(entry.path());
+      }
+    }
+
+    let mut packages = dirs
+      .into_iter()
+      .filter(|dir| !dir.is_symlink())
+      .filter_map(|dir| {
+        let content = fs::read_to_string(dir.join("package.json")).ok()?;
+        let json = serde_json::from_str::<serde_json::Value>(&content).ok()?;
+        let name = json.get("name")?.as_str()?;
+        let version = json.get("version")?.as_str()?;
+        Some((name.to_string(), version.to_string()))
+      })
+      .filter(|(name, _)| name != "npm" && name != "corepack")
+      .map(|(name, version)| format!("{}@{}", name, version))
+      .collect::<Vec<_>>();
+
+    packages.sort();
+
+    Ok(packages)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_list_global_packages() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let home = NodeVersionHome(tmp.path().to_path_buf());
+    let root = home.global_modules_dir();
+
+    assert!(home.global_packages()?.is_empty());
+
+    for (dir, name, version) in [
+      ("npm", "npm", "10.2.4"),
+      ("corepack", "corepack", "0.23.0"),
+      ("typescript", "typescript", "5.4.2"),
+      ("@vue/cli", "@vue/cli", "5.0.8"),
+    ] {
+      fs::create_dir_all(root.join(dir))?;
+      fs::write(
+        root.join(dir).join("package.json"),
+        format!(r#"{{"name":"{}","version":"{}"}}"#, name, version),
+      )?;
+    }
+
+    assert_eq!(
+      home.global_packages()?,
+      vec!["@vue/cli@5.0.8", "typescript@5.4.2"]
+    );
+
+    Ok(())
+  }
 }
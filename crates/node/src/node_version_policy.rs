@@ -0,0 +1,120 @@
+use anyhow::bail;
+use semver::Version;
+use snm_config::snm_config::SnmConfig;
+
+use crate::{NodeVersion, NodeVersionReq};
+
+/// 允许 / 禁止使用的 Node.js 版本
+///
+/// 白名单来自 `SNM_NODE_WHITE_LIST`，黑名单来自 `SNM_NODE_BLACK_LIST`，
+/// 都是逗号分隔的精确版本或 semver 范围，例如 `20.11.0, ^18.19, >=22 <23`。
+/// 白名单为空时不限制；版本同时命中白名单和黑名单时以黑名单为准。
+#[derive(Debug, Clone, Default)]
+pub struct NodeVersionPolicy {
+  allow: Vec<NodeVersionReq>,
+  deny: Vec<NodeVersionReq>,
+}
+
+impl NodeVersionPolicy {
+  pub fn from_config(config: &SnmConfig) -> anyhow::Result<Self> {
+    Ok(Self {
+      allow: Self::parse_list("SNM_NODE_WHITE_LIST", &config.node_white_list)?,
+      deny: Self::parse_list("SNM_NODE_BLACK_LIST", &config.node_black_list)?,
+    })
+  }
+
+  fn parse_list(source: &str, raw: &str) -> anyhow::Result<Vec<NodeVersionReq>> {
+    raw
+      .split(',')
+      .map(|item| item.trim())
+      .filter(|item| !item.is_empty())
+      .map(|item| {
+        let req = NodeVersionReq::try_from(item.to_string())
+          .map_err(|e| anyhow::anyhow!("Invalid entry {:?} in {}: {}", item, source, e))?;
+        match req {
+          NodeVersionReq::Exact(_) | NodeVersionReq::Range { .. } => Ok(req),
+          _ => bail!(
+            "Invalid entry {:?} in {}, only exact versions and semver ranges are supported",
+            item,
+            source
+          ),
+        }
+      })
+      .collect()
+  }
+
+  fn join(reqs: &[NodeVersionReq]) -> String {
+    reqs
+      .iter()
+      .map(|req| req.raw())
+      .collect::<Vec<_>>()
+      .join(", ")
+  }
+
+  /// 检查版本是否可用，不可用时的错误信息会带上版本和对应的配置项
+  pub fn check(&self, nv: &NodeVersion) -> anyhow::Result<()> {
+    let version = Version::parse(&nv.val)?;
+
+    if let Some(req) = self.deny.iter().find(|req| req.matches(&version)) {
+      bail!(
+        "Node.js v{} is denied by SNM_NODE_BLACK_LIST ( matched {:?} in {} )",
+        nv.val,
+        req.raw(),
+        Self::join(&self.deny)
+      );
+    }
+
+    if !self.allow.is_empty() && !self.allow.iter().any(|req| req.matches(&version)) {
+      bail!(
+        "Node.js v{} is not allowed by SNM_NODE_WHITE_LIST ( allowed: {} )",
+        nv.val,
+        Self::join(&self.allow)
+      );
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn check(allow: &str, deny: &str, version: &str) -> anyhow::Result<()> {
+    let policy = NodeVersionPolicy {
+      allow: NodeVersionPolicy::parse_list("SNM_NODE_WHITE_LIST", allow)?,
+      deny: NodeVersionPolicy::parse_list("SNM_NODE_BLACK_LIST", deny)?,
+    };
+    policy.check(&NodeVersion::try_from(version.to_string())?)
+  }
+
+  #[test]
+  fn should_allow_everything_when_empty() {
+    assert!(check("", "", "16.20.2").is_ok());
+  }
+
+  #[test]
+  fn should_enforce_allow_and_deny_lists() {
+    let allow = "20.11.0, ^18.19, >=22 <23";
+
+    assert!(check(allow, "", "20.11.0").is_ok());
+    assert!(check(allow, "", "18.20.4").is_ok());
+    assert!(check(allow, "", "22.3.0").is_ok());
+
+    let err = check(allow, "", "20.11.1").unwrap_err().to_string();
+    assert!(err.contains("v20.11.1"));
+    assert!(err.contains("SNM_NODE_WHITE_LIST"));
+
+    let err = check(allow, "18.20.0", "18.20.0").unwrap_err().to_string();
+    assert!(err.contains("SNM_NODE_BLACK_LIST"));
+
+    assert!(check("", "<18", "16.20.2").is_err());
+    assert!(check("", "<18", "18.0.0").is_ok());
+  }
+
+  #[test]
+  fn should_reject_keywords_and_aliases() {
+    assert!(check("lts/*", "", "20.11.0").is_err());
+    assert!(check("", "work", "20.11.0").is_err());
+  }
+}
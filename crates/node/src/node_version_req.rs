@@ -0,0 +1,390 @@
+use std::path::PathBuf;
+
+use anyhow::bail;
+use lazy_regex::{regex_captures, regex_is_match, regex_replace_all};
+use semver::{Version, VersionReq};
+
+use crate::{NodeMetadata, NodeVersion};
+
+/// `lts/*`、`lts/iron`、`lts/-1` 中 `/` 后面的部分
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LtsSelector {
+  /// `lts/*`：最新的 LTS 版本线
+  Newest,
+  /// `lts/-N`：从最新的 LTS 版本线往前数第 N 条
+  Offset(usize),
+  /// `lts/<codename>`：指定代号的版本线，不区分大小写
+  Codename(String),
+}
+
+/// `.node-version` 中声明的版本要求，可以是精确版本，也可以是 semver 范围
+///
+/// 范围按 npm 的语义解析：
+/// - `20` / `20.11` / `20.x` 视为前缀匹配（X-Range）
+/// - `^18.19` / `~20.1` / `>=20 <22` / `18 || 20` 与 npm 一致
+/// - `18.0.0 - 20.5` 为连字符范围
+///
+/// 另外支持和 nvm 一致的关键字：
+/// - `lts/*` / `lts/iron` / `lts/-1`，按 index.json 的 `lts` 字段匹配
+/// - `latest` / `node` / `current`，即最新发布的版本
+///
+/// 其余形如 `work`、`legacy` 的名字视为 `snm node alias` 创建的别名
+#[derive(Debug, Clone)]
+pub enum NodeVersionReq {
+  Exact(NodeVersion),
+  Range { raw: String, reqs: Vec<VersionReq> },
+  Lts { raw: String, selector: LtsSelector },
+  Latest { raw: String },
+  Alias { raw: String },
+}
+
+impl NodeVersionReq {
+  pub fn raw(&self) -> &str {
+    match self {
+      NodeVersionReq::Exact(nv) => nv.raw.as_deref().unwrap_or(&nv.val),
+      NodeVersionReq::Range { raw, .. }
+      | NodeVersionReq::Lts { raw, .. }
+      | NodeVersionReq::Latest { raw }
+      | NodeVersionReq::Alias { raw } => raw,
+    }
+  }
+
+  /// 关键字只能对照 index.json 解析，本地目录名里没有 LTS 信息
+  pub fn is_keyword(&self) -> bool {
+    matches!(
+      self,
+      NodeVersionReq::Lts { .. } | NodeVersionReq::Latest { .. }
+    )
+  }
+
+  pub fn matches(&self, version: &Version) -> bool {
+    match self {
+      NodeVersionReq::Exact(nv) => Version::parse(&nv.val).is_ok_and(|v| v.eq(version)),
+      NodeVersionReq::Range { reqs, .. } => reqs.iter().any(|req| req.matches(version)),
+      NodeVersionReq::Lts { .. } | NodeVersionReq::Latest { .. } | NodeVersionReq::Alias { .. } => {
+        false
+      }
+    }
+  }
+
+  /// 在 index.json 中挑选满足要求的最高版本
+  pub fn max_satisfying_metadata(&self, remote: &[NodeMetadata]) -> Option<Version> {
+    let versions = remote
+      .iter()
+      .filter_map(|node| Some((node, node.semver()?)))
+      .filter(|(_, version)| version.pre.is_empty());
+
+    match self {
+      NodeVersionReq::Alias { .. } => None,
+      NodeVersionReq::Exact(_) | NodeVersionReq::Range { .. } => versions
+        .filter(|(_, version)| self.matches(version))
+        .map(|(_, version)| version)
+        .max(),
+      NodeVersionReq::Latest { .. } => versions.map(|(_, version)| version).max(),
+      NodeVersionReq::Lts { selector, .. } => {
+        let lts = versions
+          .filter_map(|(node, version)| node.lts.codename().map(|name| (name, version)))
+          .collect::<Vec<_>>();
+
+        let codename = match selector {
+          LtsSelector::Codename(codename) => codename.to_string(),
+          LtsSelector::Newest => Self::lts_lines(&lts).first()?.to_string(),
+          LtsSelector::Offset(offset) => Self::lts_lines(&lts).get(*offset)?.to_string(),
+        };
+
+        lts
+          .into_iter()
+          .filter(|(name, _)| name.eq_ignore_ascii_case(&codename))
+          .map(|(_, version)| version)
+          .max()
+      }
+    }
+  }
+
+  /// 从候选版本中挑选满足要求的最高版本
+  pub fn max_satisfying<'a, I>(&self, versions: I) -> Option<Version>
+  where
+    I: IntoIterator<Item = &'a Version>,
+  {
+    versions
+      .into_iter()
+      .filter(|v| self.matches(v))
+      .max()
+      .cloned()
+  }
+
+  /// 别名以字母开头，只能包含字母、数字、`-`、`_`、`.`，且不能与关键字冲突，
+  /// 也不能被解析为版本或范围（例如 `x`、`v20`），否则 `try_from` 永远解析不到这个别名
+  pub fn is_alias_name(name: &str) -> bool {
+    regex_is_match!(r#"^[A-Za-z][A-Za-z0-9_.-]*$"#, name)
+      && NodeVersion::try_from(name.to_string()).is_err()
+      && Self::parse_keyword(name).is_ok_and(|keyword| keyword.is_none())
+      && Self::parse_range(name).is_err()
+  }
+
+  /// LTS 版本线的代号，按各自最高版本从新到旧排序
+  fn lts_lines<'a>(lts: &[(&'a str, Version)]) -> Vec<&'a str> {
+    let mut lines: Vec<(&str, &Version)> = Vec::new();
+
+    for (name, version) in lts {
+      match lines.iter_mut().find(|(n, _)| n.eq_ignore_ascii_case(name)) {
+        Some(line) if line.1 < version => line.1 = version,
+        Some(_) => {}
+        None => lines.push((name, version)),
+      }
+    }
+
+    lines.sort_by(|a, b| b.1.cmp(a.1));
+
+    lines.into_iter().map(|(name, _)| name).collect()
+  }
+
+  fn parse_keyword(raw: &str) -> anyhow::Result<Option<Self>> {
+    let is = |keyword: &str| raw.eq_ignore_ascii_case(keyword);
+
+    if is("latest") || is("node") || is("current") {
+      return Ok(Some(NodeVersionReq::Latest {
+        raw: raw.to_string(),
+      }));
+    }
+
+    let selector = if is("lts") || is("lts/*") {
+      LtsSelector::Newest
+    } else {
+      let Some(selector) = raw
+        .get(..4)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("lts/"))
+        .map(|_| &raw[4..])
+      else {
+        return Ok(None);
+      };
+
+      match selector.strip_prefix('-') {
+        Some(offset) => LtsSelector::Offset(
+          offset
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid LTS offset: {}", raw))?,
+        ),
+        None if !selector.is_empty() && selector.chars().all(|c| c.is_ascii_alphabetic()) => {
+          LtsSelector::Codename(selector.to_string())
+        }
+        None => bail!("Invalid LTS codename: {}", raw),
+      }
+    };
+
+    Ok(Some(NodeVersionReq::Lts {
+      raw: raw.to_string(),
+      selector,
+    }))
+  }
+
+  fn parse_range(raw: &str) -> anyhow::Result<Vec<VersionReq>> {
+    raw
+      .split("||")
+      .map(|set| {
+        // 把 `>= 20` 这种运算符和版本之间带空格的写法合并成一个 token
+        let set = regex_replace_all!(r#"([<>=~^]+)\s+"#, set, |_, op: &str| op.to_string());
+        let tokens = set.split_whitespace().collect::<Vec<_>>();
+
+        let comparators = match tokens.as_slice() {
+          [] => vec![String::from("*")],
+          [from, "-", to] => vec![
+            format!(">={}", from.trim_start_matches('v')),
+            format!("<={}", to.trim_start_matches('v')),
+          ],
+          tokens => tokens
+            .iter()
+            .map(|token| Self::normalize_comparator(token))
+            .collect(),
+        };
+
+        VersionReq::parse(&comparators.join(", "))
+          .map_err(|e| anyhow::anyhow!("Invalid Node.js version range: {}, {}", raw, e))
+      })
+      .collect()
+  }
+
+  fn normalize_comparator(token: &str) -> String {
+    let Some((_, op, version)) = regex_captures!(r#"^([<>=~^]*)v?(.*)$"#, token) else {
+      return token.to_string();
+    };
+
+    // npm 中不带运算符的版本是精确匹配或者前缀匹配，而 semver crate 默认是 `^`
+    if op.is_empty() && version.starts_with(|c: char| c.is_ascii_digit()) {
+      return format!("={}", version);
+    }
+
+    format!("{}{}", op, version)
+  }
+}
+
+impl TryFrom<String> for NodeVersionReq {
+  type Error = anyhow::Error;
+  fn try_from(raw: String) -> Result<Self, Self::Error> {
+    if let Ok(nv) = NodeVersion::try_from(raw.clone()) {
+      return Ok(NodeVersionReq::Exact(nv));
+    }
+
+    let raw_trim = raw.trim();
+
+    if raw_trim.is_empty() {
+      bail!("Invalid Node.js version format: {}", raw);
+    }
+
+    if let Some(req) = Self::parse_keyword(raw_trim)? {
+      return Ok(req);
+    }
+
+    match Self::parse_range(raw_trim) {
+      Ok(reqs) => Ok(NodeVersionReq::Range {
+        raw: raw_trim.to_string(),
+        reqs,
+      }),
+      Err(_) if Self::is_alias_name(raw_trim) => Ok(NodeVersionReq::Alias {
+        raw: raw_trim.to_string(),
+      }),
+      Err(e) => Err(e),
+    }
+  }
+}
+
+impl TryFrom<PathBuf> for NodeVersionReq {
+  type Error = anyhow::Error;
+  fn try_from(file: PathBuf) -> Result<Self, Self::Error> {
+    let raw = std::fs::read_to_string(&file)?;
+    Self::try_from(raw)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn versions(list: &[&str]) -> Vec<Version> {
+    list.iter().map(|v| Version::parse(v).unwrap()).collect()
+  }
+
+  fn resolve(raw: &str, list: &[&str]) -> Option<String> {
+    let req = NodeVersionReq::try_from(raw.to_string()).unwrap();
+    req.max_satisfying(&versions(list)).map(|v| v.to_string())
+  }
+
+  const CANDIDATES: &[&str] = &[
+    "18.18.0", "18.19.1", "20.0.0", "20.11.0", "20.11.1", "20.12.2", "21.7.3", "22.1.0",
+  ];
+
+  #[test]
+  fn should_keep_exact_version() -> anyhow::Result<()> {
+    let req = NodeVersionReq::try_from(String::from("v20.11.0\n"))?;
+    assert!(matches!(req, NodeVersionReq::Exact(ref nv) if nv.val == "20.11.0"));
+    Ok(())
+  }
+
+  #[test]
+  fn should_resolve_partial_versions_as_prefix() {
+    assert_eq!(resolve("20", CANDIDATES), Some("20.12.2".to_string()));
+    assert_eq!(resolve("20.11", CANDIDATES), Some("20.11.1".to_string()));
+    assert_eq!(resolve("v18\n", CANDIDATES), Some("18.19.1".to_string()));
+    assert_eq!(resolve("20.x", CANDIDATES), Some("20.12.2".to_string()));
+  }
+
+  #[test]
+  fn should_resolve_npm_style_ranges() {
+    assert_eq!(resolve("^18.19", CANDIDATES), Some("18.19.1".to_string()));
+    assert_eq!(resolve("~20.11.0", CANDIDATES), Some("20.11.1".to_string()));
+    assert_eq!(resolve(">=20 <22", CANDIDATES), Some("21.7.3".to_string()));
+    assert_eq!(
+      resolve(">= 20 < 21", CANDIDATES),
+      Some("20.12.2".to_string())
+    );
+    assert_eq!(
+      resolve("18 || 20.11", CANDIDATES),
+      Some("20.11.1".to_string())
+    );
+    assert_eq!(
+      resolve("18.0.0 - 20.11", CANDIDATES),
+      Some("20.11.1".to_string())
+    );
+  }
+
+  #[test]
+  fn should_return_none_when_nothing_matches() {
+    assert_eq!(resolve("^16", CANDIDATES), None);
+  }
+
+  fn metadata(list: &[(&str, Option<&str>)]) -> Vec<NodeMetadata> {
+    let json = list
+      .iter()
+      .map(|(version, lts)| {
+        serde_json::json!({
+          "version": format!("v{}", version),
+          "date": "2024-01-01",
+          "v8": "1.0.0",
+          "lts": lts.map(serde_json::Value::from).unwrap_or(false.into()),
+          "security": false,
+        })
+      })
+      .collect::<Vec<_>>();
+    serde_json::from_value(serde_json::Value::Array(json)).unwrap()
+  }
+
+  fn resolve_keyword(raw: &str) -> Option<String> {
+    let remote = metadata(&[
+      ("23.1.0", None),
+      ("22.11.0", Some("Jod")),
+      ("22.10.0", None),
+      ("20.18.0", Some("Iron")),
+      ("20.17.0", Some("Iron")),
+      ("18.20.4", Some("Hydrogen")),
+    ]);
+    let req = NodeVersionReq::try_from(raw.to_string()).unwrap();
+    req.max_satisfying_metadata(&remote).map(|v| v.to_string())
+  }
+
+  #[test]
+  fn should_resolve_lts_keywords() {
+    assert_eq!(resolve_keyword("lts/*"), Some("22.11.0".to_string()));
+    assert_eq!(resolve_keyword("lts/iron"), Some("20.18.0".to_string()));
+    assert_eq!(resolve_keyword("LTS/Hydrogen"), Some("18.20.4".to_string()));
+    assert_eq!(resolve_keyword("lts/-1"), Some("20.18.0".to_string()));
+    assert_eq!(resolve_keyword("lts/-2"), Some("18.20.4".to_string()));
+    assert_eq!(resolve_keyword("lts/-3"), None);
+    assert_eq!(resolve_keyword("lts/gallium"), None);
+  }
+
+  #[test]
+  fn should_resolve_latest_keywords() {
+    assert_eq!(resolve_keyword("latest"), Some("23.1.0".to_string()));
+    assert_eq!(resolve_keyword("node"), Some("23.1.0".to_string()));
+    assert_eq!(resolve_keyword("20"), Some("20.18.0".to_string()));
+  }
+
+  #[test]
+  fn should_parse_alias_names() {
+    let req = NodeVersionReq::try_from(String::from("work\n")).unwrap();
+    assert!(matches!(req, NodeVersionReq::Alias { ref raw } if raw == "work"));
+
+    assert!(NodeVersionReq::is_alias_name("legacy-18"));
+    assert!(!NodeVersionReq::is_alias_name("latest"));
+    assert!(!NodeVersionReq::is_alias_name("lts"));
+    assert!(!NodeVersionReq::is_alias_name("20"));
+  }
+
+  #[test]
+  fn should_reject_alias_names_parsed_as_versions() {
+    for name in ["x", "X", "v20", "v20.11.0", "v20.x"] {
+      assert!(!NodeVersionReq::is_alias_name(name), "{}", name);
+      assert!(!matches!(
+        NodeVersionReq::try_from(name.to_string()),
+        Ok(NodeVersionReq::Alias { .. })
+      ));
+    }
+  }
+
+  #[test]
+  fn should_reject_garbage() {
+    assert!(NodeVersionReq::try_from(String::from("lts/-x")).is_err());
+    assert!(NodeVersionReq::try_from(String::from("")).is_err());
+    assert!(NodeVersionReq::try_from(String::from("foo bar")).is_err());
+  }
+}
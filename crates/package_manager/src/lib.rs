@@ -1,7 +1,11 @@
 mod kind;
+mod lockfile;
 mod manager;
+mod remote;
 mod resolver;
 
 pub use kind::*;
+pub use lockfile::*;
 pub use manager::*;
+pub use remote::*;
 pub use resolver::*;
@@ -0,0 +1,137 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use lazy_regex::regex_captures;
+
+use crate::PackageManagerKind;
+
+/// 和 ni 一样，同一目录下有多个 lockfile 时靠前的优先
+const LOCKFILES: [&str; 6] = [
+  "bun.lockb",
+  "bun.lock",
+  "pnpm-lock.yaml",
+  "yarn.lock",
+  "package-lock.json",
+  "npm-shrinkwrap.json",
+];
+
+/// 从 lockfile 推断出的包管理器
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InferredPackageManager {
+  pub kind: PackageManagerKind,
+  /// 能读懂这个 lockfile 的主版本，推断不出时为 `None`
+  pub major: Option<u64>,
+  pub file: PathBuf,
+}
+
+impl InferredPackageManager {
+  /// `dir` 下优先级最高的 lockfile
+  pub fn find(dir: &Path) -> Option<PathBuf> {
+    LOCKFILES
+      .iter()
+      .map(|name| dir.join(name))
+      .find(|file| file.is_file())
+  }
+
+  /// 在 `dir` 下查找 lockfile 并推断包管理器，没有 lockfile 或者是 bun 的 lockfile 时返回 `None`
+  pub fn detect(dir: &Path) -> anyhow::Result<Option<Self>> {
+    let Some(file) = Self::find(dir) else {
+      return Ok(None);
+    };
+
+    let name = file
+      .file_name()
+      .map(|name| name.to_string_lossy().into_owned())
+      .unwrap_or_default();
+
+    let (kind, major) = match name.as_str() {
+      // bun 不由 snm 管理，交给下一级兜底
+      "bun.lockb" | "bun.lock" => return Ok(None),
+      "pnpm-lock.yaml" => (
+        PackageManagerKind::Pnpm,
+        pnpm_major(&fs::read_to_string(&file)?),
+      ),
+      "yarn.lock" => (
+        PackageManagerKind::Yarn,
+        Some(yarn_major(&fs::read_to_string(&file)?)),
+      ),
+      _ => (PackageManagerKind::Npm, None),
+    };
+
+    Ok(Some(Self { kind, major, file }))
+  }
+}
+
+/// pnpm-lock.yaml 的 `lockfileVersion` 对应的 pnpm 主版本，pnpm 10 仍然写 9.0，按 9 处理
+fn pnpm_major(content: &str) -> Option<u64> {
+  let (_, version) = regex_captures!(r#"(?m)^lockfileVersion:\s*['"]?([\d.]+)['"]?"#, content)?;
+
+  match version {
+    "5.3" => Some(6),
+    "5.4" => Some(7),
+    "6.0" | "6.1" => Some(8),
+    "9.0" => Some(9),
+    _ => None,
+  }
+}
+
+/// yarn.lock 带有 `__metadata` 的是 berry，`__metadata.version` 对应 berry 的主版本，否则是 classic
+fn yarn_major(content: &str) -> u64 {
+  let Some((_, version)) = regex_captures!(r#"(?m)^__metadata:\s*\n\s+version:\s*(\d+)"#, content)
+  else {
+    return 1;
+  };
+
+  match version {
+    "4" => 2,
+    "5" | "6" => 3,
+    _ => 4,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_not_infer_from_bun_lockfile() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::write(dir.path().join("bun.lock"), "{}")?;
+    fs::write(dir.path().join("package-lock.json"), "{}")?;
+
+    assert_eq!(
+      InferredPackageManager::find(dir.path()),
+      Some(dir.path().join("bun.lock"))
+    );
+    assert_eq!(InferredPackageManager::detect(dir.path())?, None);
+    Ok(())
+  }
+
+  #[test]
+  fn should_infer_pnpm_major() {
+    assert_eq!(pnpm_major("lockfileVersion: '9.0'\n\nsettings:\n"), Some(9));
+    assert_eq!(pnpm_major("lockfileVersion: '6.0'\n"), Some(8));
+    assert_eq!(pnpm_major("lockfileVersion: 5.4\n"), Some(7));
+    assert_eq!(pnpm_major("lockfileVersion: 5.3\n"), Some(6));
+    assert_eq!(pnpm_major("lockfileVersion: '3.0'\n"), None);
+    assert_eq!(pnpm_major("importers:\n"), None);
+  }
+
+  #[test]
+  fn should_infer_yarn_major() {
+    let classic =
+      "# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.\n# yarn lockfile v1\n";
+    assert_eq!(yarn_major(classic), 1);
+
+    let berry = |version: u64| {
+      format!(
+        "# This file is generated by running \"yarn install\"\n\n__metadata:\n  version: {version}\n  cacheKey: 10c0\n"
+      )
+    };
+    assert_eq!(yarn_major(&berry(4)), 2);
+    assert_eq!(yarn_major(&berry(6)), 3);
+    assert_eq!(yarn_major(&berry(8)), 4);
+  }
+}
@@ -1,19 +1,82 @@
 use std::str::FromStr;
 
-use anyhow::bail;
+use anyhow::{Context, bail};
 use lazy_regex::regex_captures;
+use snm_downloader::PinnedDigest;
+use strum::EnumString;
 
 use crate::PackageManagerKind;
 
+/// corepack 风格 packageManager 中 `+<algorithm>.<digest>` 使用的摘要算法
+#[derive(Debug, PartialEq, Eq, Clone, Copy, strum::Display, EnumString, strum::AsRefStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum HashAlgorithm {
+  Sha1,
+  Sha224,
+  Sha256,
+  Sha512,
+}
+
+/// packageManager 中固定的 tarball 摘要，例如 `pnpm@9.1.0+sha512.<hex>`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PackageManagerHash {
+  pub algorithm: HashAlgorithm,
+  /// 十六进制摘要
+  pub digest: String,
+}
+
+impl PackageManagerHash {
+  pub fn pinned_digest(&self) -> PinnedDigest {
+    match self.algorithm {
+      HashAlgorithm::Sha1 => PinnedDigest::Sha1(self.digest.clone()),
+      HashAlgorithm::Sha224 => PinnedDigest::Sha224(self.digest.clone()),
+      HashAlgorithm::Sha256 => PinnedDigest::Sha256(self.digest.clone()),
+      HashAlgorithm::Sha512 => PinnedDigest::Sha512(self.digest.clone()),
+    }
+  }
+}
+
+impl FromStr for PackageManagerHash {
+  type Err = anyhow::Error;
+
+  fn from_str(raw: &str) -> Result<Self, Self::Err> {
+    let Some((_, algorithm, digest)) = regex_captures!(r#"^([a-z0-9]+)\.([0-9a-fA-F]+)$"#, raw)
+    else {
+      bail!("Failed to capture package manager hash: {}", raw);
+    };
+
+    let algorithm = HashAlgorithm::from_str(algorithm).map_err(|_| {
+      anyhow::anyhow!(
+        "Unsupported package manager hash algorithm: {}, expected sha1, sha224, sha256 or sha512",
+        algorithm
+      )
+    })?;
+
+    Ok(Self {
+      algorithm,
+      digest: digest.to_string(),
+    })
+  }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct PackageManager {
   kind: PackageManagerKind,
   version: String,
+  hash: Option<PackageManagerHash>,
 }
 
 impl PackageManager {
   pub fn new(kind: PackageManagerKind, version: String) -> Self {
-    Self { kind, version }
+    Self {
+      kind,
+      version,
+      hash: None,
+    }
+  }
+
+  pub fn with_hash(self, hash: Option<PackageManagerHash>) -> Self {
+    Self { hash, ..self }
   }
 
   pub fn kind(&self) -> PackageManagerKind {
@@ -27,14 +90,19 @@ impl PackageManager {
   pub fn version(&self) -> &str {
     &self.version
   }
+
+  /// packageManager 中固定的摘要，下载后除了 registry 的校验和之外还要满足它
+  pub fn hash(&self) -> Option<&PackageManagerHash> {
+    self.hash.as_ref()
+  }
 }
 
 impl FromStr for PackageManager {
   type Err = anyhow::Error;
 
   fn from_str(raw: &str) -> Result<Self, Self::Err> {
-    let Some((_, name, version)) = regex_captures!(
-      r#"^(?P<name>npm|yarn|pnpm)@(?P<version>[^+]+)(?:\+.+)?$"#,
+    let Some((_, name, version, hash)) = regex_captures!(
+      r#"^(?P<name>npm|yarn|pnpm)@(?P<version>[^+]+)(?:\+(?P<hash>.+))?$"#,
       raw
     ) else {
       bail!("Failed to capture package manager: {}", raw);
@@ -43,6 +111,78 @@ impl FromStr for PackageManager {
     let kind = PackageManagerKind::try_from(name)
       .map_err(|_| anyhow::anyhow!("Unsupported package manager: {}, Raw: {}", name, raw))?;
 
-    Ok(PackageManager::new(kind, version.to_string()))
+    let hash = match hash {
+      "" => None,
+      hash => Some(
+        PackageManagerHash::from_str(hash)
+          .with_context(|| format!("Invalid packageManager: {}", raw))?,
+      ),
+    };
+
+    Ok(PackageManager::new(kind, version.to_string()).with_hash(hash))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_keep_package_manager_hash() -> anyhow::Result<()> {
+    let package_manager = PackageManager::from_str("pnpm@9.1.0+sha512.2bd9a0f3e7cdb1a5c0e0f8d2")?;
+
+    assert_eq!(package_manager.version(), "9.1.0");
+    assert_eq!(
+      package_manager.hash(),
+      Some(&PackageManagerHash {
+        algorithm: HashAlgorithm::Sha512,
+        digest: String::from("2bd9a0f3e7cdb1a5c0e0f8d2"),
+      })
+    );
+
+    assert_eq!(PackageManager::from_str("yarn@4.1.0")?.hash(), None);
+    assert!(PackageManager::from_str("pnpm@9.1.0+md5.abcdef").is_err());
+    assert!(PackageManager::from_str("pnpm@9.1.0+sha512").is_err());
+
+    Ok(())
+  }
+
+  #[test]
+  fn should_verify_sha224_written_by_yarn() -> anyhow::Result<()> {
+    // `yarn set version 4.11.0` 写入的值，sha224 是 bin/yarn.js 的摘要
+    let package_manager = PackageManager::from_str(
+      "yarn@4.11.0+sha224.209a3e277c6bbc03df6e4206fbfcb0c1621c27ecf0688f79a0c619f0",
+    )?;
+
+    let Some(hash) = package_manager.hash() else {
+      panic!("expected a hash");
+    };
+    assert_eq!(hash.algorithm, HashAlgorithm::Sha224);
+    assert_eq!(
+      hash.pinned_digest(),
+      PinnedDigest::Sha224(String::from(
+        "209a3e277c6bbc03df6e4206fbfcb0c1621c27ecf0688f79a0c619f0"
+      ))
+    );
+
+    // "hello" 的 sha224
+    let package_manager = PackageManager::from_str(
+      "yarn@4.1.0+sha224.ea09ae9cc6768c50fcee903ed054556e5bfc8347907f12598aa24193",
+    )?;
+
+    let Some(hash) = package_manager.hash() else {
+      panic!("expected a hash");
+    };
+
+    let dir = tempfile::tempdir()?;
+    let bundle = dir.path().join("yarn.js");
+
+    std::fs::write(&bundle, "hello")?;
+    assert!(hash.pinned_digest().verify(&bundle)?);
+
+    std::fs::write(&bundle, "hello!")?;
+    assert!(!hash.pinned_digest().verify(&bundle)?);
+
+    Ok(())
   }
 }
@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, de::IgnoredAny};
+use snm_config::snm_config::SnmConfig;
+use snm_downloader::MetadataCache;
+
+use crate::PackageManagerKind;
+
+/// registry 精简 packument 中用到的部分
+#[derive(Debug, Deserialize)]
+struct Packument {
+  #[serde(rename = "dist-tags", default)]
+  dist_tags: BTreeMap<String, String>,
+  #[serde(default)]
+  versions: BTreeMap<String, IgnoredAny>,
+}
+
+/// 某种包管理器在 registry 上发布过的版本
+///
+/// yarn 1.x 发布为 `yarn`，2.x 及以上发布为 `@yarnpkg/cli-dist`，两者合并，dist-tag 优先取 `yarn`
+#[derive(Debug, Default)]
+pub struct RemoteVersions {
+  /// 按 semver 从旧到新排序
+  pub versions: Vec<Version>,
+  pub tags: BTreeMap<String, String>,
+}
+
+impl RemoteVersions {
+  pub async fn fetch(
+    config: &SnmConfig,
+    kind: &PackageManagerKind,
+    refresh: bool,
+  ) -> anyhow::Result<Self> {
+    let names: &[&str] = match kind {
+      PackageManagerKind::Npm => &["npm"],
+      PackageManagerKind::Pnpm => &["pnpm"],
+      PackageManagerKind::Yarn => &["yarn", "@yarnpkg/cli-dist"],
+    };
+
+    let cache =
+      MetadataCache::new(config, refresh).with_accept("application/vnd.npm.install-v1+json");
+
+    let mut remote = Self::default();
+
+    for name in names {
+      let url = format!("{}/{}", config.npm_registry, name);
+
+      let packument = cache
+        .get_json::<Packument>(
+          &format!(
+            "packument-{}.json",
+            name
+              .trim_start_matches('@')
+              .split('/')
+              .collect::<Vec<_>>()
+              .join("-")
+          ),
+          &url,
+        )
+        .await
+        .with_context(|| format!("Failed to fetch {}", url))?;
+
+      remote.versions.extend(
+        packument
+          .versions
+          .into_keys()
+          .filter_map(|version| Version::parse(&version).ok()),
+      );
+
+      for (tag, version) in packument.dist_tags {
+        remote.tags.entry(tag).or_insert(version);
+      }
+    }
+
+    remote.versions.sort();
+    remote.versions.dedup();
+
+    Ok(remote)
+  }
+
+  /// dist-tag 对应的版本，或满足范围的最高正式版本，`spec` 既不是 dist-tag 也不是合法范围时报错
+  pub fn resolve(&self, spec: &str) -> anyhow::Result<Option<String>> {
+    if let Some(version) = self.tags.get(spec) {
+      return Ok(Some(version.clone()));
+    }
+
+    let req = VersionReq::parse(spec)
+      .with_context(|| format!("Invalid version, range or dist-tag: {}", spec))?;
+
+    Ok(
+      self
+        .versions
+        .iter()
+        .filter(|version| version.pre.is_empty() && req.matches(version))
+        .max()
+        .map(|version| version.to_string()),
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_resolve_tags_and_ranges() -> anyhow::Result<()> {
+    let remote = RemoteVersions {
+      versions: ["8.15.9", "9.0.0-rc.0", "9.1.0", "9.12.3", "10.0.0"]
+        .iter()
+        .map(|version| Version::parse(version))
+        .collect::<Result<_, _>>()?,
+      tags: BTreeMap::from([(String::from("latest"), String::from("10.0.0"))]),
+    };
+
+    assert_eq!(remote.resolve("latest")?, Some(String::from("10.0.0")));
+    assert_eq!(remote.resolve("^9")?, Some(String::from("9.12.3")));
+    assert_eq!(remote.resolve("^7")?, None);
+    assert!(remote.resolve("next").is_err());
+
+    Ok(())
+  }
+}
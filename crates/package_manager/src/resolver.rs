@@ -1,12 +1,35 @@
 use std::{path::PathBuf, str::FromStr};
 
-use anyhow::bail;
+use anyhow::{Context, bail};
+use lazy_regex::regex_captures;
 use package_json_parser::PackageJsonParser;
+use semver::{Version, VersionReq};
 use snm_config::snm_config::SnmConfig;
 use snm_downloader::{DownloadPackageManagerResource, download_resource};
 use up_finder::UpFinder;
 
-use crate::PackageManager;
+use crate::{InferredPackageManager, PackageManager, PackageManagerKind, RemoteVersions};
+
+/// `req` 为 `None` 时任何合法的版本都满足
+fn matches_req(req: Option<&VersionReq>, version: &str) -> bool {
+  Version::parse(version).is_ok_and(|version| req.is_none_or(|req| req.matches(&version)))
+}
+
+/// 拆分 `SNM_DEFAULT_PACKAGE_MANAGER`，只写名称时 spec 为空字符串
+fn parse_configured_default(raw: &str) -> anyhow::Result<(PackageManagerKind, String)> {
+  let Some((_, name, spec)) = regex_captures!(r#"^(npm|yarn|pnpm)(?:@([^+]+))?$"#, raw) else {
+    bail!(
+      "Invalid SNM_DEFAULT_PACKAGE_MANAGER: {}, expected <name>, <name>@<version> or <name>@<range>",
+      raw
+    );
+  };
+
+  if !spec.is_empty() && Version::parse(spec).is_err() && VersionReq::parse(spec).is_err() {
+    bail!("Invalid SNM_DEFAULT_PACKAGE_MANAGER: {}", raw);
+  }
+
+  Ok((PackageManagerKind::from_str(name)?, spec.to_string()))
+}
 
 pub struct PackageManagerResolver {
   pub config: SnmConfig,
@@ -18,19 +41,174 @@ impl From<SnmConfig> for PackageManagerResolver {
   }
 }
 
+/// 包管理器是怎么确定的
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackageManagerSource {
+  /// package.json 的 packageManager 字段
+  PackageJson(PathBuf),
+  /// 没有声明 packageManager，从这个 lockfile 推断
+  Lockfile(PathBuf),
+  /// 没有声明 packageManager，使用 `SNM_DEFAULT_PACKAGE_MANAGER` 的原始配置
+  ConfiguredDefault(String),
+  /// 没有声明也没有配置默认包管理器，使用当前 Node.js 自带的 npm
+  BundledNpm,
+}
+
+impl PackageManagerSource {
+  /// 使用了哪种兜底，声明了 packageManager 时返回 `None`
+  pub fn fallback(&self) -> Option<String> {
+    match self {
+      PackageManagerSource::PackageJson(_) => None,
+      PackageManagerSource::Lockfile(file) => Some(format!("lockfile {:?}", file)),
+      PackageManagerSource::ConfiguredDefault(raw) => {
+        Some(format!("SNM_DEFAULT_PACKAGE_MANAGER={}", raw))
+      }
+      PackageManagerSource::BundledNpm => Some(String::from("npm bundled with Node.js")),
+    }
+  }
+}
+
+/// 不访问网络就能确定的包管理器来源，shim 据此判断当前命令是否需要接管
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackageManagerRequest {
+  /// package.json 的 packageManager 字段
+  Declared(PackageManager, PathBuf),
+  /// 没有声明 packageManager，从 lockfile 推断
+  Lockfile(InferredPackageManager),
+  /// 没有声明 packageManager，`SNM_DEFAULT_PACKAGE_MANAGER` 配置的版本、范围或只有名称（`spec` 为空）
+  ConfiguredDefault {
+    raw: String,
+    kind: PackageManagerKind,
+    spec: String,
+  },
+  /// 没有声明也没有配置默认包管理器，使用当前 Node.js 自带的 npm
+  BundledNpm,
+}
+
+impl PackageManagerRequest {
+  /// 由 snm 管理的包管理器种类，使用 Node.js 自带的 npm（包括 npm 的 lockfile）时返回 `None`
+  pub fn kind(&self) -> Option<PackageManagerKind> {
+    match self {
+      PackageManagerRequest::Declared(package_manager, _) => Some(package_manager.kind()),
+      PackageManagerRequest::Lockfile(inferred) => {
+        (inferred.kind != PackageManagerKind::Npm).then(|| inferred.kind.clone())
+      }
+      PackageManagerRequest::ConfiguredDefault { kind, .. } => Some(kind.clone()),
+      PackageManagerRequest::BundledNpm => None,
+    }
+  }
+
+  pub fn source(&self) -> PackageManagerSource {
+    match self {
+      PackageManagerRequest::Declared(_, file) => PackageManagerSource::PackageJson(file.clone()),
+      PackageManagerRequest::Lockfile(inferred) => {
+        PackageManagerSource::Lockfile(inferred.file.clone())
+      }
+      PackageManagerRequest::ConfiguredDefault { raw, .. } => {
+        PackageManagerSource::ConfiguredDefault(raw.clone())
+      }
+      PackageManagerRequest::BundledNpm => PackageManagerSource::BundledNpm,
+    }
+  }
+}
+
+/// `resolve_package_manager` 的结果，使用 Node.js 自带的 npm（包括 npm 的 lockfile）时 `package_manager` 为 `None`
+#[derive(Debug, Clone)]
+pub struct ResolvedPackageManager {
+  pub package_manager: Option<PackageManager>,
+  pub source: PackageManagerSource,
+}
+
 impl PackageManagerResolver {
   pub fn find_up_package_manager(&self) -> anyhow::Result<PackageManager> {
-    let find_up = UpFinder::builder().cwd(&self.config.workspace).build();
+    self
+      .find_up_package_manager_with_path()
+      .map(|(package_manager, _)| package_manager)
+  }
 
-    let files = find_up.find_up("package.json");
+  /// 同 `find_up_package_manager`，额外返回声明了 packageManager 的 package.json 路径
+  pub fn find_up_package_manager_with_path(&self) -> anyhow::Result<(PackageManager, PathBuf)> {
+    let Some(declared) = self.find_declared()? else {
+      bail!("You have not correctly configured packageManager in package.json");
+    };
 
-    if files.is_empty() {
-      if self.config.strict {
-        bail!("You have not correctly configured packageManager in package.json");
-      }
+    Ok(declared)
+  }
+
+  /// 确定当前项目使用的包管理器，等同于 `find_request` 之后 `resolve_request`
+  pub async fn resolve_package_manager(&self) -> anyhow::Result<ResolvedPackageManager> {
+    let request = self.find_request()?;
+
+    self.resolve_request(request).await
+  }
+
+  /// 确定包管理器的来源，不访问网络
+  ///
+  /// 优先使用 package.json 中声明的 packageManager；没有声明时严格模式直接报错，
+  /// 非严格模式下依次兜底到 lockfile 推断、`SNM_DEFAULT_PACKAGE_MANAGER` 和 Node.js 自带的 npm
+  pub fn find_request(&self) -> anyhow::Result<PackageManagerRequest> {
+    if let Some((package_manager, file)) = self.find_declared()? {
+      return Ok(PackageManagerRequest::Declared(package_manager, file));
+    }
+
+    if self.config.strict {
+      bail!("You have not correctly configured packageManager in package.json");
+    }
+
+    if let Some(inferred) = self.find_lockfile()? {
+      return Ok(PackageManagerRequest::Lockfile(inferred));
     }
 
-    let Some(package_manager_raw) = files.iter().find_map(|item| {
+    if let Some(raw) = &self.config.default_package_manager {
+      let (kind, spec) = parse_configured_default(raw)?;
+      return Ok(PackageManagerRequest::ConfiguredDefault {
+        raw: raw.to_owned(),
+        kind,
+        spec,
+      });
+    }
+
+    Ok(PackageManagerRequest::BundledNpm)
+  }
+
+  /// 把 `find_request` 的结果解析为具体版本，范围和 dist-tag 在没有满足的已安装版本时对照 registry 解析
+  pub async fn resolve_request(
+    &self,
+    request: PackageManagerRequest,
+  ) -> anyhow::Result<ResolvedPackageManager> {
+    let source = request.source();
+
+    let package_manager = match request {
+      PackageManagerRequest::Declared(package_manager, _) => Some(package_manager),
+      PackageManagerRequest::Lockfile(inferred) => self.resolve_inferred(inferred).await?,
+      PackageManagerRequest::ConfiguredDefault { raw, kind, spec } => Some(
+        self
+          .resolve_configured_default(&kind, &spec)
+          .await
+          .with_context(|| format!("Failed to resolve SNM_DEFAULT_PACKAGE_MANAGER={}", raw))?,
+      ),
+      PackageManagerRequest::BundledNpm => None,
+    };
+
+    Ok(ResolvedPackageManager {
+      package_manager,
+      source,
+    })
+  }
+
+  /// 是否有 package.json 声明了 packageManager，不管声明的值能否解析
+  ///
+  /// 没有声明时 `resolve_package_manager` 的错误都来自兜底，`snm which` 需要把它报告出来而不是当作透传
+  pub fn is_declared(&self) -> bool {
+    self.find_declared_raw().is_some()
+  }
+
+  fn find_declared_raw(&self) -> Option<(String, PathBuf)> {
+    let find_up = UpFinder::builder().cwd(&self.config.workspace).build();
+
+    let files = find_up.find_up("package.json");
+
+    files.iter().find_map(|item| {
       let Ok(package_json) = PackageJsonParser::parse(item) else {
         return None;
       };
@@ -39,36 +217,259 @@ impl PackageManagerResolver {
         return None;
       };
 
-      Some(raw)
-    }) else {
-      // TODO 未来可能在非严格模式下会尝试存在默认的包管理器
-      if self.config.strict {
-        bail!("You have not correctly configured packageManager in package.json");
-      } else {
-        bail!("You have not correctly configured packageManager in package.json");
+      Some((raw.0, item.to_owned()))
+    })
+  }
+
+  fn find_declared(&self) -> anyhow::Result<Option<(PackageManager, PathBuf)>> {
+    let Some((package_manager_raw, file)) = self.find_declared_raw() else {
+      return Ok(None);
+    };
+
+    let package_manager = PackageManager::from_str(&package_manager_raw)?;
+
+    Ok(Some((package_manager, file)))
+  }
+
+  /// 从最近的 package.json 所在目录开始向上查找 lockfile，monorepo 子包使用根目录的 lockfile
+  fn find_lockfile(&self) -> anyhow::Result<Option<InferredPackageManager>> {
+    let find_up = UpFinder::builder().cwd(&self.config.workspace).build();
+
+    for file in find_up.find_up("package.json") {
+      let Some(dir) = file.parent() else {
+        continue;
+      };
+
+      // 最近的 lockfile 决定推断结果，即使推断不出（例如 bun）也不再向上查找
+      if InferredPackageManager::find(dir).is_some() {
+        return InferredPackageManager::detect(dir);
       }
+    }
+
+    Ok(None)
+  }
+
+  /// npm 的 lockfile 使用 Node.js 自带的 npm，返回 `None`；pnpm、yarn 在主版本兼容的前提下，
+  /// 依次使用同种类的 `SNM_DEFAULT_PACKAGE_MANAGER`、`snm pm default` 设置的版本和已安装的最新版本，
+  /// 都没有时从 registry 解析出兼容的最新版本，由 `ensure_package_manager` 按 `SNM_AUTO_INSTALL` 安装
+  ///
+  /// lockfile 只能由它自己的包管理器读写，推断出 pnpm、yarn 后不会再换成其他包管理器
+  async fn resolve_inferred(
+    &self,
+    inferred: InferredPackageManager,
+  ) -> anyhow::Result<Option<PackageManager>> {
+    if inferred.kind == PackageManagerKind::Npm {
+      return Ok(None);
+    }
+
+    let spec = inferred
+      .major
+      .map_or(String::from("latest"), |major| format!("^{}", major));
+
+    let req = inferred
+      .major
+      .map(|major| VersionReq::parse(&format!("^{}", major)))
+      .transpose()?;
+
+    if let Some(raw) = &self.config.default_package_manager {
+      let (kind, configured_spec) = parse_configured_default(raw)?;
+      if kind == inferred.kind {
+        if let Some(package_manager) = self
+          .find_configured_default(&kind, &configured_spec)?
+          .filter(|package_manager| matches_req(req.as_ref(), package_manager.version()))
+        {
+          return Ok(Some(package_manager));
+        }
+      }
+    }
+
+    if let Some(package_manager) = self.pick_installed(&inferred.kind, req.as_ref())? {
+      return Ok(Some(package_manager));
+    }
+
+    let package_manager = self
+      .resolve_remote(&inferred.kind, &spec)
+      .await
+      .with_context(|| {
+        format!(
+          "Detected {}@{} from {:?}, but no matching version is installed",
+          inferred.kind, spec, inferred.file
+        )
+      })?;
+
+    Ok(Some(package_manager))
+  }
+
+  /// 对照 registry 把范围或 dist-tag 解析为具体版本
+  async fn resolve_remote(
+    &self,
+    kind: &PackageManagerKind,
+    spec: &str,
+  ) -> anyhow::Result<PackageManager> {
+    let Some(version) = RemoteVersions::fetch(&self.config, kind, false)
+      .await?
+      .resolve(spec)?
+    else {
+      bail!("No published version matches {}@{}", kind, spec);
     };
 
-    let package_manager = PackageManager::from_str(&package_manager_raw.0)?;
+    Ok(PackageManager::new(kind.clone(), version))
+  }
 
-    Ok(package_manager)
+  /// `pnpm@9.1.0` 直接使用；`pnpm@9.x` 在已安装的版本中选择；
+  /// 只写 `pnpm` 时使用 `snm pm default` 设置的版本，没有设置时使用已安装的最新版本
+  ///
+  /// 没有满足的已安装版本时返回 `None`
+  fn find_configured_default(
+    &self,
+    kind: &PackageManagerKind,
+    spec: &str,
+  ) -> anyhow::Result<Option<PackageManager>> {
+    if Version::parse(spec).is_ok() {
+      return Ok(Some(PackageManager::new(kind.clone(), spec.to_string())));
+    }
+
+    let req = match spec {
+      "" => None,
+      spec => Some(VersionReq::parse(spec)?),
+    };
+
+    self.pick_installed(kind, req.as_ref())
   }
 
-  pub async fn ensure_package_manager(
+  /// 同 `find_configured_default`，没有满足的已安装版本时和 packageManager 一样从 registry 解析，
+  /// 只写名称时使用 `latest`，之后由 `ensure_package_manager` 按 `SNM_AUTO_INSTALL` 安装
+  async fn resolve_configured_default(
     &self,
-    package_manager: &PackageManager,
-  ) -> anyhow::Result<PathBuf> {
-    let mut dir = self
+    kind: &PackageManagerKind,
+    spec: &str,
+  ) -> anyhow::Result<PackageManager> {
+    if let Some(package_manager) = self.find_configured_default(kind, spec)? {
+      return Ok(package_manager);
+    }
+
+    let spec = match spec {
+      "" => "latest",
+      spec => spec,
+    };
+
+    self.resolve_remote(kind, spec).await
+  }
+
+  /// 在已安装的版本中选择满足 `req` 的版本，`snm pm default` 设置的版本满足时优先，其次是最新版本
+  fn pick_installed(
+    &self,
+    kind: &PackageManagerKind,
+    req: Option<&VersionReq>,
+  ) -> anyhow::Result<Option<PackageManager>> {
+    if let Some(package_manager) = self
+      .default_package_manager(kind)
+      .filter(|package_manager| matches_req(req, package_manager.version()))
+    {
+      return Ok(Some(package_manager));
+    }
+
+    let version = self
+      .installed_versions(kind)?
+      .into_iter()
+      .rev()
+      .find(|version| matches_req(req, version));
+
+    Ok(version.map(|version| PackageManager::new(kind.clone(), version)))
+  }
+
+  /// 已安装的版本，按 semver 从旧到新排序，不含 `snm pm default` 的软链
+  pub fn installed_versions(&self, kind: &PackageManagerKind) -> anyhow::Result<Vec<String>> {
+    let dir = self.config.node_modules_dir.join(kind.as_ref());
+
+    if !dir.try_exists()? {
+      return Ok(vec![]);
+    }
+
+    let mut versions = dir
+      .read_dir()?
+      .filter_map(|entry| entry.ok())
+      .filter(|entry| entry.file_name() != "default")
+      .filter(|entry| entry.path().join("package.json").is_file())
+      .map(|entry| entry.file_name().to_string_lossy().into_owned())
+      .collect::<Vec<_>>();
+
+    versions.sort_by_cached_key(|version| Version::parse(version).ok());
+
+    Ok(versions)
+  }
+
+  /// 包管理器的安装目录，目录下的 package.json 存在即视为已安装
+  pub fn package_manager_dir(&self, package_manager: &PackageManager) -> PathBuf {
+    self
       .config
       .node_modules_dir
       .join(package_manager.name())
-      .join(package_manager.version());
+      .join(package_manager.version())
+  }
+
+  /// `snm pm default` 为每种包管理器设置的默认版本，是指向 node_modules_dir/<name>/<version> 的软链
+  pub fn default_dir(&self, kind: &PackageManagerKind) -> PathBuf {
+    self
+      .config
+      .node_modules_dir
+      .join(kind.as_ref())
+      .join("default")
+  }
+
+  /// 读取 `snm pm default` 设置的默认版本，没有设置时返回 `None`
+  pub fn default_package_manager(&self, kind: &PackageManagerKind) -> Option<PackageManager> {
+    let version = self
+      .default_dir(kind)
+      .read_link()
+      .ok()?
+      .file_name()?
+      .to_string_lossy()
+      .into_owned();
+
+    Some(PackageManager::new(kind.clone(), version))
+  }
+
+  /// 已安装的包管理器中 `bin_name` 对应的脚本路径，未安装时返回 `None`
+  pub fn bin_path(
+    &self,
+    package_manager: &PackageManager,
+    bin_name: &str,
+  ) -> anyhow::Result<Option<PathBuf>> {
+    let dir = self.package_manager_dir(package_manager);
     let file = dir.join("package.json");
+
     if !file.try_exists()? {
+      return Ok(None);
+    }
+
+    let json = PackageJsonParser::parse(file)?;
+
+    Ok(
+      json
+        .bin_to_hash_map()?
+        .get(bin_name)
+        .map(|bin| dir.join(bin)),
+    )
+  }
+
+  pub async fn ensure_package_manager(
+    &self,
+    package_manager: &PackageManager,
+  ) -> anyhow::Result<PathBuf> {
+    let mut dir = self.package_manager_dir(package_manager);
+    let file = dir.join("package.json");
+    if !file.try_exists()? {
+      let spec = format!("{}@{}", package_manager.name(), package_manager.version());
+      self
+        .config
+        .check_auto_install(&spec, &format!("snm pm install {}", spec))?;
+
       let resource = DownloadPackageManagerResource::builder()
         .config(&self.config)
         .bin_name(package_manager.name().to_string())
         .version(package_manager.version().to_string())
+        .pinned_digest(package_manager.hash().map(|hash| hash.pinned_digest()))
         .build();
 
       dir = download_resource(resource).await?;
@@ -76,3 +477,103 @@ impl PackageManagerResolver {
     Ok(dir)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+
+  use snm_test_utils::SnmTestContext;
+  use test_context::test_context;
+
+  use super::*;
+
+  fn resolver(ctx: &mut SnmTestContext, default: &str) -> anyhow::Result<PackageManagerResolver> {
+    ctx.set_envs(&[
+      (
+        format!("{}_HOME_DIR", ctx.get_id()),
+        ctx.get_temp_dir().to_string_lossy().to_string(),
+      ),
+      (
+        format!("{}_DEFAULT_PACKAGE_MANAGER", ctx.get_id()),
+        default.to_string(),
+      ),
+    ]);
+
+    let config = SnmConfig::from(ctx.get_id(), ctx.get_temp_dir())?;
+
+    Ok(PackageManagerResolver::from(config))
+  }
+
+  #[test_context(SnmTestContext)]
+  #[tokio::test]
+  async fn should_resolve_configured_range_from_installed(
+    ctx: &mut SnmTestContext,
+  ) -> anyhow::Result<()> {
+    fs::write(
+      ctx.get_temp_dir().join("package.json"),
+      r#"{"name":"demo"}"#,
+    )?;
+
+    let resolver = resolver(ctx, "pnpm@9.x")?;
+
+    let installed = resolver.config.node_modules_dir.join("pnpm").join("9.1.0");
+    fs::create_dir_all(&installed)?;
+    fs::write(installed.join("package.json"), "{}")?;
+
+    let request = resolver.find_request()?;
+
+    assert_eq!(request.kind(), Some(PackageManagerKind::Pnpm));
+    assert_eq!(
+      request.source(),
+      PackageManagerSource::ConfiguredDefault(String::from("pnpm@9.x"))
+    );
+
+    let resolved = resolver.resolve_request(request).await?;
+
+    assert_eq!(
+      resolved.package_manager,
+      Some(PackageManager::new(
+        PackageManagerKind::Pnpm,
+        String::from("9.1.0")
+      ))
+    );
+
+    Ok(())
+  }
+
+  #[test_context(SnmTestContext)]
+  #[test]
+  fn should_keep_npm_lockfile_on_bundled_npm(ctx: &mut SnmTestContext) -> anyhow::Result<()> {
+    fs::write(
+      ctx.get_temp_dir().join("package.json"),
+      r#"{"name":"demo"}"#,
+    )?;
+    fs::write(ctx.get_temp_dir().join("package-lock.json"), "{}")?;
+
+    let resolver = resolver(ctx, "pnpm@9.x")?;
+
+    let request = resolver.find_request()?;
+
+    assert!(matches!(request, PackageManagerRequest::Lockfile(_)));
+    assert_eq!(request.kind(), None);
+
+    Ok(())
+  }
+
+  #[test_context(SnmTestContext)]
+  #[test]
+  fn should_reject_invalid_configured_default(ctx: &mut SnmTestContext) -> anyhow::Result<()> {
+    fs::write(
+      ctx.get_temp_dir().join("package.json"),
+      r#"{"name":"demo"}"#,
+    )?;
+
+    for raw in ["bun", "pnpm@nine"] {
+      let resolver = resolver(ctx, raw)?;
+
+      assert!(resolver.find_request().is_err(), "{}", raw);
+    }
+
+    Ok(())
+  }
+}
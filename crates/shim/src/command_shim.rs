@@ -5,6 +5,7 @@ use std::{
 
 use anyhow::bail;
 use snm_config::snm_config::SnmConfig;
+use snm_node::NodeUsage;
 use snm_utils::consts::SNM_PREFIX;
 use tracing::trace;
 
@@ -39,11 +40,22 @@ impl CommandShim {
 
     let snm_config = SnmConfig::from(SNM_PREFIX, &cwd)?;
 
+    // CI 日志里颜色控制符只会变成乱码
+    if snm_config.ci {
+      colored::control::set_override(false);
+    }
+
     let node_resolver = snm_node::NodeResolver::from(snm_config.clone());
 
-    let bin_dir = node_resolver.resolve_node_bin_dir().await?;
+    let resolution = node_resolver.resolve_node().await?;
+
+    // 使用记录只服务于 `snm node prune`，写入失败不影响命令执行
+    let project = resolution.file.as_ref().and_then(|file| file.parent());
+    if let Err(e) = NodeUsage::new(&snm_config).record(&resolution.version, project) {
+      trace!("Failed to record Node.js usage: {:?}", e);
+    }
 
-    let paths = vec![bin_dir.to_string_lossy().into_owned()];
+    let paths = vec![resolution.bin_dir.to_string_lossy().into_owned()];
 
     if actual_bin_name == "node" {
       Ok(CommandShim::Node(NodeShim::new(args, paths)))
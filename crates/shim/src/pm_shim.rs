@@ -4,7 +4,9 @@ use anyhow::bail;
 use colored::Colorize;
 use package_json_parser::PackageJsonParser;
 use snm_config::snm_config::SnmConfig;
+use snm_package_manager::PackageManagerRequest;
 use snm_utils::exec::exec_cli;
+use tracing::trace;
 
 pub struct PmShim {
   pub args: Vec<String>,
@@ -22,41 +24,15 @@ impl PmShim {
   }
 
   pub async fn proxy(&self) -> anyhow::Result<()> {
-    let [bin_name, command, args @ ..] = self.args.as_slice() else {
+    let [raw_bin_name, command, args @ ..] = self.args.as_slice() else {
       bail!(r#"deconstruct args failed, args: {:?}"#, self.args);
     };
 
-    let resolver = snm_package_manager::PackageManagerResolver::from(self.snm_config.clone());
-
-    let Ok(package_manager) = resolver.find_up_package_manager() else {
-      // 考虑到 npx 这种情况，找不要必须要透传
-      return exec_cli(
-        &[&[bin_name.clone(), command.to_owned()], args].concat(),
-        &self.paths,
-        true,
-      );
-    };
-
-    // let Some(spm) = SPM::from_config_file(&self.snm_config) else {
-    //   if self.snm_config.strict {
-    //     bail!("You have not correctly configured packageManager in package.json");
-    //   }
-    //   return exec_cli(
-    //     &[&[bin_name.clone(), command.to_owned()], args].concat(),
-    //     &self.paths,
-    //     true,
-    //   );
-    // };
-
-    // 处理配置了包管理器的情况
-    // let spm = SPM::try_from(&self.snm_config.workspace, &self.snm_config)?;
-    // let pm = &spm.pm;
-
     // 传进来的有可能是绝对路径, 如果是绝对路径的的话，取 file_name 判断一下。
     // 同时需要保证直取命令的名称，方便 后续的 json.get_bin_with_name(bin_name) 获取到对应 js 的真实路径
     // 主要用来拦截处理 snm 自己创建的 symlink , windows 下 symlink 拿到的是绝对路径
-    let bin_name = if Path::new(bin_name).is_absolute() {
-      Path::new(bin_name)
+    let bin_name = if Path::new(raw_bin_name).is_absolute() {
+      Path::new(raw_bin_name)
         .file_name()
         .and_then(|f| f.to_str())
         .map(|name| {
@@ -65,17 +41,59 @@ impl PmShim {
             .or_else(|| name.strip_suffix(".exe"))
             .unwrap_or(name)
         })
-        .unwrap_or(bin_name)
+        .unwrap_or(raw_bin_name)
     } else {
-      bin_name
+      raw_bin_name
+    };
+
+    let resolver = snm_package_manager::PackageManagerResolver::from(self.snm_config.clone());
+
+    let passthrough = |reason: &dyn std::fmt::Debug| {
+      trace!("Passthrough {}: {:?}", bin_name, reason);
+      exec_cli(
+        &[&[raw_bin_name.clone(), command.to_owned()], args].concat(),
+        &self.paths,
+        true,
+      )
+    };
+
+    let request = match resolver.find_request() {
+      Ok(request) => request,
+      // 没有声明 packageManager 时错误来自 lockfile 或 SNM_DEFAULT_PACKAGE_MANAGER，需要报告
+      Err(e) if !self.snm_config.strict && !resolver.is_declared() => return Err(e),
+      // 考虑到 npx 这种情况，找不要必须要透传
+      Err(e) => return passthrough(&e),
+    };
+
+    let owns = |name: &str| [name, "npx", "pnpx"].contains(&bin_name);
+
+    if let PackageManagerRequest::Declared(package_manager, _) = &request {
+      if !owns(package_manager.name()) {
+        bail!(
+          "Package manager mismatch, expect: {}, actual: {}",
+          package_manager.name().green(),
+          bin_name.red()
+        );
+      }
+    }
+
+    // 兜底的包管理器只接管同名命令，其他命令和 Node.js 自带的 npm 一样透传，不会访问 registry
+    if !request.kind().is_some_and(|kind| owns(kind.as_ref())) {
+      return passthrough(&request);
+    }
+
+    let resolved = resolver.resolve_request(request).await?;
+
+    let Some(package_manager) = resolved.package_manager else {
+      return passthrough(&resolved);
     };
 
-    let matched = &[package_manager.name(), "npx", "pnpx"].contains(&bin_name);
-    if !matched {
-      bail!(
-        "Package manager mismatch, expect: {}, actual: {}",
-        package_manager.name().green(),
-        bin_name.red()
+    if let Some(fallback) = resolved.source.fallback() {
+      trace!(
+        "packageManager is not declared, using {}@{} ( {} )",
+        package_manager.name(),
+        package_manager.version(),
+        fallback
       );
     }
 
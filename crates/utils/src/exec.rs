@@ -7,13 +7,23 @@ use anyhow::bail;
 use tracing::trace;
 
 pub fn exec_cli(args: &Vec<String>, paths: &Vec<String>, check_snm: bool) -> anyhow::Result<()> {
+  exec_cli_with_stdout(args, paths, check_snm, Stdio::inherit())
+}
+
+/// 和 `exec_cli` 相同，但子进程的 stdout 写到 `stdout`，例如 `--json` 时改写到 stderr
+pub fn exec_cli_with_stdout(
+  args: &[String],
+  paths: &[String],
+  check_snm: bool,
+  stdout: Stdio,
+) -> anyhow::Result<()> {
   trace!("exec_cli args: {:#?}", args);
 
-  let [bin_name, args @ ..] = args.as_slice() else {
+  let [bin_name, args @ ..] = args else {
     bail!("No binary name provided in arguments");
   };
 
-  let new_path = create_path_with_additional_dirs(paths.clone())?;
+  let new_path = create_path_with_additional_dirs(paths.to_vec())?;
 
   let cwd = std::env::current_dir()?;
 
@@ -35,7 +45,7 @@ pub fn exec_cli(args: &Vec<String>, paths: &Vec<String>, check_snm: bool) -> any
     let output = Command::new(program)
       .args(args)
       .env("PATH", new_path.clone())
-      .stdout(Stdio::inherit())
+      .stdout(stdout)
       .stderr(Stdio::inherit())
       .stdin(Stdio::inherit())
       .output()?;
@@ -2,3 +2,4 @@ pub mod consts;
 pub mod exec;
 pub mod log;
 pub mod ver;
+pub mod write;
@@ -0,0 +1,26 @@
+use std::{fs, path::Path, process};
+
+use anyhow::Context;
+
+/// 先写到同目录下的临时文件再 `rename` 覆盖，中断或并发时不会留下写了一半的文件
+pub fn write_atomic(path: &Path, content: impl AsRef<[u8]>) -> anyhow::Result<()> {
+  let dir = path
+    .parent()
+    .with_context(|| format!("Can not get the parent directory of {:?}", path))?;
+
+  let file_name = path
+    .file_name()
+    .with_context(|| format!("Can not get the file name of {:?}", path))?
+    .to_string_lossy();
+
+  let tmp = dir.join(format!(".{}.{}.tmp", file_name, process::id()));
+
+  fs::write(&tmp, content)?;
+
+  if let Err(e) = fs::rename(&tmp, path) {
+    let _ = fs::remove_file(&tmp);
+    return Err(e.into());
+  }
+
+  Ok(())
+}